@@ -1,20 +1,141 @@
-use church::{Body, Term};
-use once_cell::sync::Lazy;
-use std::{collections::HashMap, sync::atomic::AtomicUsize};
+use church::{alias::Naming, encoding::Encoding, strategy::Eta, AlphaTerm, Body, Fuel, Ref, Term};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::AtomicUsize,
+};
 
-use crate::{Ast, Error, Result};
+use crate::{deps, Ast, Error, Result};
 
 #[derive(Default, Clone)]
 pub struct Compiler {
+    /// The closed definitions are references, so they're shared by their uses
     pub defs: HashMap<String, Term>,
-    pub aliases: HashMap<Term, String>,
+    pub aliases: HashMap<AlphaTerm, String>,
+    /// Names of the variables, when showing terms and reading free variables
+    pub naming: Naming,
+    /// Free variables of the compiled terms, so no binder captures them
+    frees: HashSet<usize>,
 }
 
 impl Compiler {
     pub fn dump(&mut self, t: &Ast) -> Result<Term> {
+        self.reserve_frees(t);
         self.dump_with(&mut HashMap::new(), t)
     }
 
+    /// Keeps the free variables of `t`, also the ones of the open definitions
+    /// it uses, out of the fresh binders
+    fn reserve_frees(&mut self, t: &Ast) {
+        for name in t.free_names() {
+            match self.defs.get(name) {
+                Some(def) => self.frees.extend(def.free_vars()),
+                None => self.frees.extend(self.get_idx(name)),
+            }
+        }
+    }
+
+    /// Defines the definitions of `group`, that may refer to each other. Closed
+    /// ones are references, that refer to each other by name, and open ones are
+    /// compiled by `Compiler::dump_group`.
+    pub fn define_group(&mut self, group: &[(&str, &Ast)]) -> Result<()> {
+        if let Some(chain) = deps::head_cycle(group) {
+            return Err(Error::HeadCycle(chain));
+        }
+        if let Some((name, def)) = group
+            .iter()
+            .find_map(|(name, _)| Some((*name, self.definition(name)?)))
+        {
+            return Err(Error::AlreadyDefined(name.to_owned(), def.clone()));
+        }
+        let refs: Vec<_> = group.iter().map(|(name, _)| Ref::declare(*name)).collect();
+        for ((name, _), r) in group.iter().zip(&refs) {
+            self.defs
+                .insert(name.to_string(), Body::Ref(r.clone()).into());
+        }
+        let dumps: Result<Vec<_>> = group.iter().map(|(_, t)| self.dump(t)).collect();
+        for (name, _) in group {
+            self.defs.remove(*name);
+        }
+        let dumps = dumps?;
+        if !dumps.iter().all(Term::is_closed) {
+            let dumps = self.dump_group(group)?;
+            for ((name, _), dump) in group.iter().zip(dumps) {
+                self.insert_def(name.to_string(), dump)?;
+            }
+            return Ok(());
+        }
+        for (r, dump) in refs.iter().zip(dumps) {
+            // just declared, so it isn't defined yet
+            let _ = r.define(dump);
+        }
+        for ((name, _), r) in group.iter().zip(refs) {
+            self.insert_def(name.to_string(), Body::Ref(r).into())?;
+        }
+        Ok(())
+    }
+
+    /// Compiles the definition `name = t`, through a fixpoint if it refers to itself
+    pub fn dump_def(&mut self, name: &str, t: &Ast) -> Result<Term> {
+        if !t.free_names().contains(name) {
+            return self.dump(t);
+        }
+        self.reserve_frees(t);
+        let rec = self.fresh_ident();
+        let m = self.dump_with(&mut HashMap::from([(name.to_owned(), rec)]), t)?;
+        Ok(Body::App(Term::fixpoint(), Body::Abs(rec, m).into()).into())
+    }
+
+    /// Compiles definitions that refer to each other, each one is a projection
+    /// of the fixpoint of their tuple, `λs . s M₁ … Mₙ`
+    pub fn dump_group(&mut self, group: &[(&str, &Ast)]) -> Result<Vec<Term>> {
+        if let Some(chain) = deps::head_cycle(group) {
+            return Err(Error::HeadCycle(chain));
+        }
+        if let [(name, t)] = group {
+            return Ok(vec![self.dump_def(name, t)?]);
+        }
+        for (_, t) in group {
+            self.reserve_frees(t);
+        }
+        let recs: Vec<_> = group.iter().map(|_| self.fresh_ident()).collect();
+        let mut ctx = group
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .zip(recs.iter().copied())
+            .collect();
+        let [tuple, s] = [(); 2].map(|_| self.fresh_ident());
+        let var = |v: usize| Term::from(Body::Var(v));
+        let mut body = var(s);
+        for (_, t) in group {
+            // binds the names of the group to the projections of the tuple
+            let mut m = self.dump_with(&mut ctx, t)?;
+            for rec in recs.iter().rev() {
+                m = Body::Abs(*rec, m).into();
+            }
+            for i in 0..group.len() {
+                let projection = Body::App(var(tuple), Self::projection(i, group.len()));
+                m = Body::App(m, projection.into()).into();
+            }
+            body = Body::App(body, m).into();
+        }
+        let fix: Term = Body::App(
+            Term::fixpoint(),
+            Body::Abs(tuple, Body::Abs(s, body).into()).into(),
+        )
+        .into();
+        Ok((0..group.len())
+            .map(|i| Body::App(fix.clone(), Self::projection(i, group.len())).into())
+            .collect())
+    }
+
+    /// `λx₀ … xₙ₋₁ . xᵢ`
+    fn projection(i: usize, n: usize) -> Term {
+        let xs: Vec<_> = (0..n).map(|_| Self::get_new_ident()).collect();
+        xs.iter()
+            .rev()
+            .fold(Body::Var(xs[i]).into(), |m, x| Body::Abs(*x, m).into())
+    }
+
     fn dump_with(&mut self, ctx: &mut HashMap<String, usize>, t: &Ast) -> Result<Term> {
         match t {
             Ast::Var(v) => self.get_var_def(ctx, v),
@@ -25,7 +146,7 @@ impl Compiler {
                 Ok(Term::from(b))
             }
             Ast::Abs(v, m) => {
-                let v_alias = Self::get_new_ident();
+                let v_alias = self.fresh_ident();
                 let old = ctx.insert(v.clone(), v_alias);
                 let m = self.dump_with(ctx, m)?;
                 if let Some(old) = old {
@@ -39,41 +160,29 @@ impl Compiler {
         }
     }
 
+    /// Compiles `let x = M, … in (N)` as `(λx . …N) M`, so each binding is seen
+    /// by the next ones and by the body, in the scope where the `let` is
     fn dump_let(
         &mut self,
         ctx: &mut HashMap<String, usize>,
         defs: &[Ast],
         m: &Ast,
     ) -> Result<Term> {
-        let mut olds = Vec::new();
-        for component in defs {
-            if let Ast::Assign(id, def) = component {
-                if let Some(old_bind) = self.defs.remove(id) {
-                    let old_alias = self.aliases.remove(&old_bind);
-                    olds.push((Some(old_bind), old_alias));
-                } else {
-                    olds.push((None, None));
-                }
-                let dump = self.dump(def)?;
-                self.insert(id.clone(), dump)?;
-            } else {
-                unreachable!()
-            }
-        }
-        let term = self.dump_with(ctx, m)?;
-        for (component, (old_bind, old_alias)) in defs.iter().zip(olds) {
-            if let Ast::Assign(id, _) = component {
-                if let Some(bind) = old_bind {
-                    *self.aliases.get_mut(&bind).unwrap() = old_alias.unwrap();
-                    *self.defs.get_mut(id).unwrap() = bind;
-                } else {
-                    self.defs.remove(id);
-                }
-            } else {
-                unreachable!()
-            }
-        }
-        Ok(term)
+        let Some((component, rest)) = defs.split_first() else {
+            return self.dump_with(ctx, m);
+        };
+        let Ast::Assign(id, def) = component else {
+            unreachable!()
+        };
+        let def = self.dump_with(ctx, def)?;
+        let v_alias = self.fresh_ident();
+        let old = ctx.insert(id.clone(), v_alias);
+        let body = self.dump_let(ctx, rest, m);
+        match old {
+            Some(old) => ctx.insert(id.clone(), old),
+            None => ctx.remove(id),
+        };
+        Ok(Body::App(Body::Abs(v_alias, body?).into(), def).into())
     }
 
     fn get_var_def(&self, ctx: &mut HashMap<String, usize>, v: &str) -> Result<Term> {
@@ -82,45 +191,12 @@ impl Compiler {
                 || self.defs.get(v).cloned(),
                 |v| Some(Term::from(Body::Var(*v))),
             )
-            .or_else(|| Self::into_free_var(v))
+            .or_else(|| self.free_var(v))
             .ok_or_else(|| Error::DefNotFound(v.to_string()))
     }
 
-    //#[cfg(not(feature = "aliased-vars"))]
-    //fn get_idx(f: impl Iterator<Item = char>) -> Option<usize> {
-    //    todo!()
-    //}
-
-    //#[cfg(feature = "aliased-vars")]
-    fn get_idx<I>(s: I) -> Option<usize>
-    where
-        I: Iterator<Item = char> + DoubleEndedIterator,
-    {
-        const ALIASES: &[char] = &[
-            'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ', 'σ',
-            'ς', 'τ', 'υ', 'φ', 'χ', 'ψ', 'ω', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j',
-            'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0',
-            '1', '2', '3', '4', '5', '6', '7', '8', '9',
-        ];
-        static LAZY_MAP: Lazy<HashMap<char, usize>> = Lazy::new(|| {
-            ALIASES
-                .iter()
-                .copied()
-                .enumerate()
-                .map(|(i, c)| (c, i))
-                .collect()
-        });
-
-        let mut counter = 0;
-        for c in s.rev() {
-            let n = LAZY_MAP.get(&c)?;
-            counter = counter * 10 + n;
-        }
-        Some(counter)
-    }
-
-    fn into_free_var(s: &str) -> Option<Term> {
-        let t = Body::Var(Self::get_idx(s.chars())?);
+    fn free_var(&self, s: &str) -> Option<Term> {
+        let t = Body::Var(self.get_idx(s)?);
         Some(t.into())
     }
 
@@ -129,49 +205,102 @@ impl Compiler {
         ID_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
     }
 
+    /// A binder that isn't any of the free variables
+    fn fresh_ident(&self) -> usize {
+        loop {
+            let v = Self::get_new_ident();
+            if !self.frees.contains(&v) {
+                return v;
+            }
+        }
+    }
+
+    /// Defines `name`, as a reference when `def` is closed
     pub fn insert(&mut self, name: String, def: Term) -> Result<()> {
-        if self.defs.contains_key(&name) {
-            let t = self.defs[&name].clone();
-            Err(Error::AlreadyDefined(name, t))
+        if def.is_closed() {
+            let r = Ref::new(name.clone(), def);
+            self.insert_def(name, Body::Ref(r).into())
         } else {
-            self.aliases
-                .insert(def.coerce(Term::unique_alpha_redex), name.clone());
-            self.defs.insert(name, def);
+            self.insert_def(name, def)
+        }
+    }
+
+    fn insert_def(&mut self, name: String, def: Term) -> Result<()> {
+        if let Some(t) = self.definition(&name) {
+            return Err(Error::AlreadyDefined(name, t.clone()));
+        }
+        // Reductions unfold the references, so the aliases are of the inlined terms
+        let plain = def.inline_refs();
+        // So it's recognized after η-reductions too
+        self.aliases
+            .entry(AlphaTerm(Self::eta_normal(&plain)))
+            .or_insert_with(|| name.clone());
+        self.aliases.insert(AlphaTerm(plain), name.clone());
+        self.defs.insert(name, def);
+        Ok(())
+    }
 
-            Ok(())
+    /// The term that `name` is defined as, out of its reference
+    pub fn definition(&self, name: &str) -> Option<&Term> {
+        let def = self.defs.get(name)?;
+        match def.body.as_ref() {
+            Body::Ref(r) => Some(r.term()),
+            _ => Some(def),
         }
     }
 
+    fn eta_normal(t: &Term) -> Term {
+        t.coerce(|t| {
+            t.normalize(&Eta, Fuel::UNBOUNDED);
+        })
+    }
+
     pub fn pretty_show(&self, t: &Term) -> String {
-        if let Some(s) = self.aliases.get(&t.coerce(Term::unique_alpha_redex)) {
+        // the encodings are recognized with the references unfolded
+        self.pretty_with(t, &t.inline_refs())
+    }
+
+    /// Shows `t`, whose references are unfolded in `plain`
+    fn pretty_with(&self, t: &Term, plain: &Term) -> String {
+        if let Body::Ref(r) = t.body.as_ref() {
+            return r.name().to_owned();
+        }
+        if let Some(s) = self.aliases.get(AlphaTerm::from_ref(plain)) {
             s.clone()
+        } else if let Some(items) = plain.as_church_list() {
+            self.pretty_list(&items)
+        } else if let Some(n) = Encoding::ALL.iter().find_map(|e| e.as_nat(plain)) {
+            // numerals before the other lists, as `0` is also their `[]`
+            n.to_string()
+        } else if let Some(items) = Encoding::ALL.iter().find_map(|e| e.as_list(plain)) {
+            self.pretty_list(&items)
+        } else if let Some((a, b)) = plain.as_church_pair() {
+            format!("({}, {})", self.pretty_with(a, a), self.pretty_with(b, b))
         } else {
-            match t.body.as_ref() {
-                Body::Var(v) => Self::get_alias(*v),
-                Body::App(m, n) => format!("{} {}", self.pretty_show(m), self.pretty_show(n)),
-                Body::Abs(v, m) => {
-                    format!("λ{} {}", Self::get_alias(*v), self.pretty_show(m))
+            match (t.body.as_ref(), plain.body.as_ref()) {
+                (Body::Var(v), _) => self.get_alias(*v),
+                (Body::App(m, n), Body::App(pm, pn)) => {
+                    format!("{} {}", self.pretty_with(m, pm), self.pretty_with(n, pn))
+                }
+                (Body::Abs(v, m), Body::Abs(_, pm)) => {
+                    format!("λ{} {}", self.get_alias(*v), self.pretty_with(m, pm))
                 }
+                _ => unreachable!("just the references are unfolded"),
             }
         }
     }
 
-    pub fn get_alias(var: usize) -> String {
-        const ALIASES: &[char] = &[
-            'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ', 'σ',
-            'ς', 'τ', 'υ', 'φ', 'χ', 'ψ', 'ω', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j',
-            'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0',
-            '1', '2', '3', '4', '5', '6', '7', '8', '9',
-        ];
-        let mut counter = var;
-        let mut s = String::with_capacity(var / ALIASES.len());
-        loop {
-            let idx = counter % ALIASES.len();
-            s.push(ALIASES[idx]);
-            counter /= ALIASES.len();
-            if counter == 0 {
-                return s;
-            }
-        }
+    fn pretty_list(&self, items: &[&Term]) -> String {
+        let items: Vec<_> = items.iter().map(|i| self.pretty_with(i, i)).collect();
+        format!("[{}]", items.join(", "))
+    }
+
+    pub fn get_alias(&self, var: usize) -> String {
+        self.naming.name(var)
+    }
+
+    /// The free variable named `s`
+    pub fn get_idx(&self, s: &str) -> Option<usize> {
+        self.naming.index(s)
     }
 }
@@ -1,32 +1,78 @@
+use crate::compiler::Compiler;
 use crate::grammar::ProgramParser;
 use crate::parser::{ParserToken, Token};
-use crate::compiler::Compiler;
-use crate::{Ast, Error};
+use crate::{deps, Ast, Error};
+use church::{blc::BlcError, Term};
 use logos::Logos;
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+#[derive(Default)]
 pub struct CodeUnit {
     pub scope: Compiler,
     pub program_parser: ProgramParser,
-}
-
-impl Default for CodeUnit {
-    fn default() -> Self {
-        Self {
-            scope: Compiler::default(),
-            program_parser: ProgramParser::new(),
-        }
-    }
+    /// Files being loaded, each one used by the previous
+    loading: Vec<PathBuf>,
+    /// Files already loaded, so the files used by several ones are loaded once
+    loaded: HashSet<PathBuf>,
 }
 
 impl CodeUnit {
     pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
-        let content = fs::read_to_string(path.as_ref())
+        let path = fs::canonicalize(path.as_ref())
             .map_err(|_| Error::ModuleNotFound(path.as_ref().into()))?;
+        if let Some(start) = self.loading.iter().position(|p| *p == path) {
+            let mut chain = self.loading[start..].to_vec();
+            chain.push(path);
+            return Err(Error::UseCycle(chain));
+        }
+        if self.loaded.contains(&path) {
+            return Ok(());
+        }
+        self.loading.push(path.clone());
+        let res = self.read_file(&path);
+        self.loading.pop();
+        if res.is_ok() {
+            self.loaded.insert(path);
+        }
+        res
+    }
+
+    /// A path of a `use` is relative to the file being loaded, if any
+    fn resolve(&self, path: &str) -> PathBuf {
+        match self.loading.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.join(path),
+            None => path.into(),
+        }
+    }
+
+    fn read_file(&mut self, path: &Path) -> Result<(), Error> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("blc") => return self.load_blc(path, false),
+            Some("Blc") => return self.load_blc(path, true),
+            _ => (),
+        }
+        let content = fs::read_to_string(path).map_err(|_| Error::ModuleNotFound(path.into()))?;
         let program = self.parse(&content)?;
-        self.eval(program).unwrap();
-        Ok(())
+        self.eval(program)
+    }
+
+    /// Defines the term of a BLC file, named by the file, so
+    /// `use "lib/succ.blc"` defines `succ`. As in Tromp's tools, a `.blc` file
+    /// is written as bits and a `.Blc` one is packed in bytes.
+    fn load_blc(&mut self, path: &Path, packed: bool) -> Result<(), Error> {
+        let content = fs::read(path).map_err(|_| Error::ModuleNotFound(path.into()))?;
+        let t = if packed {
+            Term::from_blc_bytes(&content)
+        } else {
+            std::str::from_utf8(&content)
+                .map_err(|e| BlcError::BadChar(e.valid_up_to()))
+                .and_then(Term::from_blc_bits)
+        }
+        .map_err(|e| Error::InvalidBlc(path.into(), e))?;
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        self.scope.insert(name, t)
     }
 
     pub fn into_raw_tokens(src: &str) -> Result<Vec<ParserToken>, Error> {
@@ -36,7 +82,7 @@ impl CodeUnit {
                 ac.push((sp.start, tk?, sp.end));
                 Ok(ac)
             })
-            .map_err(|e| Error::LexerError(e))
+            .map_err(Error::LexerError)
     }
 
     pub fn into_tokens(src: &str) -> Result<impl Iterator<Item = ParserToken>, Error> {
@@ -49,24 +95,238 @@ impl CodeUnit {
         let ast = self
             .program_parser
             .parse(iter)
-            .map_err(|e| Error::ParserError(e))?;
+            .map_err(Error::ParserError)?;
         Ok(ast)
     }
 
     pub fn eval(&mut self, program: Ast) -> Result<(), Error> {
         match program {
             Ast::Program(p) => {
-                for atom in p {
-                    self.eval(atom)?;
+                let defs = p
+                    .iter()
+                    .filter_map(|atom| match atom {
+                        Ast::Assign(v, m) => Some(Ok((v.as_str(), m.as_ref()))),
+                        Ast::Use(..) => None,
+                        other => Some(Err(Error::NotADefinition(format!("{other:?}")))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                // the definitions of the used files are visible to the whole file
+                for atom in &p {
+                    if let Ast::Use(path) = atom {
+                        self.load_file(self.resolve(path))?;
+                    }
+                }
+                for group in deps::groups(&defs) {
+                    let group: Vec<_> = group.iter().map(|i| defs[*i]).collect();
+                    self.scope.define_group(&group)?;
                 }
             }
-            Ast::Assign(v, m) => {
-                let dump = self.scope.dump(&m)?;
-                self.scope.insert(v, dump)?;
-            }
-            Ast::Use(path) => self.load_file(path)?,
-            _ => unreachable!(),
+            Ast::Assign(v, m) => self.scope.define_group(&[(&v, &m)])?,
+            Ast::Use(path) => self.load_file(self.resolve(&path))?,
+            other => return Err(Error::NotADefinition(format!("{other:?}"))),
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CodeUnit;
+    use crate::{grammar::ExprParser, Ast};
+    use church::{
+        assert_alpha_eq,
+        path::Step::{self, Arg, Fun},
+        strategy::Normal,
+        Body, Fuel, Outcome, Path, ReductionStrategy, Term,
+    };
+
+    fn dump(cu: &mut CodeUnit, src: &str) -> Term {
+        let tks = CodeUnit::into_tokens(src).unwrap();
+        let expr = ExprParser::new().parse(tks).unwrap();
+        cu.scope.dump(&expr).unwrap()
+    }
+
+    fn eval(cu: &mut CodeUnit, src: &str) -> Term {
+        let mut t = dump(cu, src);
+        let res = t.normalize(&Normal, Fuel::steps(10_000));
+        assert_eq!(res.outcome, Outcome::Normal);
+        t
+    }
+
+    #[test]
+    fn recursive_definitions() {
+        let mut cu = CodeUnit::default();
+        cu.load_file(concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/list.ch"))
+            .unwrap();
+        let l = "(Cons a (Cons b Nil))";
+        assert_alpha_eq!(
+            eval(&mut cu, &format!("Head (Tail (Map g {l}))")),
+            eval(&mut cu, "g b")
+        );
+        assert_alpha_eq!(
+            eval(&mut cu, &format!("Foldr z g {l}")),
+            eval(&mut cu, "g (Pair (g (Pair z b)) a)")
+        );
+    }
+
+    #[test]
+    fn shared_definitions() {
+        let mut cu = CodeUnit::default();
+        cu.load_file(concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/list.ch"))
+            .unwrap();
+        let tks = CodeUnit::into_tokens("Map g (Cons a Nil)").unwrap();
+        let mut t = cu
+            .scope
+            .dump(&ExprParser::new().parse(tks).unwrap())
+            .unwrap();
+        assert_eq!(t.size(), 9);
+        // unfolds `Map` and applies it, to `IsNil l Nil (Cons (g (Head l)) (Map g (Tail l)))`,
+        // whose recursive call is folded, referring to the same definitions
+        for _ in 0..3 {
+            assert!(!Normal.step(&mut t));
+        }
+        let ref_at = |steps: &[Step]| match t.subterm(&Path(steps.to_vec())) {
+            Some(Term { body }) => match body.as_ref() {
+                Body::Ref(r) => r.clone(),
+                other => panic!("{other:?} isn't a reference"),
+            },
+            None => panic!("{steps:?} isn't in the term"),
+        };
+        let Body::Ref(map) = cu.scope.defs["Map"].body.as_ref() else {
+            unreachable!("`Map` is closed")
+        };
+        let Body::Ref(tail) = cu.scope.defs["Tail"].body.as_ref() else {
+            unreachable!("`Tail` is closed")
+        };
+        // `Ref`s are equal when they share their definition
+        assert_eq!(&ref_at(&[Arg, Arg, Fun, Fun]), map);
+        assert_eq!(&ref_at(&[Arg, Arg, Arg, Fun]), tail);
+    }
+
+    #[test]
+    fn local_let() {
+        let mut cu = CodeUnit::default();
+        let program = cu
+            .parse("Id = fn x => x\nDup = fn x => (let y = x, z = y in (z y))")
+            .unwrap();
+        cu.eval(program).unwrap();
+        // each binding sees the enclosing binders and the previous bindings
+        assert_alpha_eq!(eval(&mut cu, "Dup b"), eval(&mut cu, "b b"));
+        // and shadows the definitions, without replacing them
+        assert_alpha_eq!(eval(&mut cu, "let Id = a in (Id b)"), eval(&mut cu, "a b"));
+        assert_alpha_eq!(eval(&mut cu, "Id b"), eval(&mut cu, "b"));
+        assert_eq!(cu.scope.defs.len(), 2);
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        let mut cu = CodeUnit::default();
+        cu.load_file(concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/list.ch"))
+            .unwrap();
+        let program = cu
+            .parse("Even l = IsNil l True (Odd (Tail l))\nOdd l = IsNil l False (Even (Tail l))")
+            .unwrap();
+        cu.eval(program).unwrap();
+        let l = "(Cons a (Cons b (Cons c Nil)))";
+        assert_alpha_eq!(eval(&mut cu, &format!("Odd {l}")), eval(&mut cu, "True"));
+        assert_alpha_eq!(eval(&mut cu, &format!("Even {l}")), eval(&mut cu, "False"));
+
+        // refers to the free `x`, so it's compiled through a fixpoint of a tuple
+        let program = cu
+            .parse("P l = IsNil l x (Q (Tail l))\nQ l = IsNil l (x x) (P (Tail l))")
+            .unwrap();
+        cu.eval(program).unwrap();
+        assert!(!matches!(cu.scope.defs["P"].body.as_ref(), Body::Ref(..)));
+        assert_alpha_eq!(eval(&mut cu, &format!("P {l}")), eval(&mut cu, "x x"));
+        assert_alpha_eq!(eval(&mut cu, &format!("Q {l}")), eval(&mut cu, "x"));
+
+        let program = cu.parse("A = B x\nB = C\nC = B y").unwrap();
+        assert_eq!(
+            cu.eval(program).unwrap_err().to_string(),
+            "`B -> C -> B` unfolds to itself before reaching an abstraction, so it can't be compiled"
+        );
+    }
+
+    #[test]
+    fn forward_references() {
+        let mut cu = CodeUnit::default();
+        let program = cu.parse("A = B x\nB = C\nC = fn y => y").unwrap();
+        cu.eval(program).unwrap();
+        assert_alpha_eq!(eval(&mut cu, "A"), eval(&mut cu, "x"));
+
+        let program = Ast::Program(vec![Ast::Var("D".into())]);
+        assert_eq!(
+            cu.eval(program).unwrap_err().to_string(),
+            "a program is made of definitions and `use`s, not Var(\"D\")"
+        );
+
+        let dir = std::env::temp_dir().join(format!("church-use-cycle-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = std::fs::canonicalize(dir).unwrap();
+        let (a, b) = (dir.join("a.ch"), dir.join("b.ch"));
+        std::fs::write(&a, format!("use \"{}\"\nA = B", b.display())).unwrap();
+        std::fs::write(&b, format!("use \"{}\"\nB = A", a.display())).unwrap();
+        let err = CodeUnit::default().load_file(&a).unwrap_err();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            err.to_string(),
+            format!("{0} -> {1} -> {0} use each other", a.display(), b.display())
+        );
+    }
+
+    #[test]
+    fn shared_uses() {
+        let dir = std::env::temp_dir().join(format!("church-shared-use-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("a.ch"), "use \"lib/c.ch\"\nA = C").unwrap();
+        std::fs::write(dir.join("b.ch"), "use \"lib/c.ch\"\nB = C").unwrap();
+        std::fs::write(dir.join("lib/c.ch"), "C = fn x => x").unwrap();
+        // the paths are relative to the file using them, and `c.ch` is loaded once
+        let mut cu = CodeUnit::default();
+        let res = (
+            cu.load_file(dir.join("a.ch")),
+            cu.load_file(dir.join("b.ch")),
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(matches!(res, (Ok(()), Ok(()))), "{res:?}");
+        assert_alpha_eq!(eval(&mut cu, "A y"), eval(&mut cu, "B y"));
+    }
+
+    #[test]
+    fn blc_modules() {
+        let dir = std::env::temp_dir().join(format!("church-blc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // λx . x as bits, and λx y . x packed in a byte
+        std::fs::write(dir.join("i.blc"), "00 10\n").unwrap();
+        std::fs::write(dir.join("k.Blc"), [0b0000_1100]).unwrap();
+        // λx . x packed in a byte, that's a space
+        std::fs::write(dir.join("id.Blc"), [0b0010_0000]).unwrap();
+        std::fs::write(
+            dir.join("main.ch"),
+            "use \"i.blc\"\nuse \"k.Blc\"\nuse \"id.Blc\"",
+        )
+        .unwrap();
+        let mut cu = CodeUnit::default();
+        let res = cu.load_file(dir.join("main.ch"));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(res.is_ok(), "{res:?}");
+        assert_alpha_eq!(eval(&mut cu, "i x"), eval(&mut cu, "x"));
+        assert_alpha_eq!(eval(&mut cu, "k x y"), eval(&mut cu, "x"));
+        assert_alpha_eq!(eval(&mut cu, "id x"), eval(&mut cu, "x"));
+    }
+
+    #[test]
+    fn free_variables_are_not_captured() {
+        let mut cu = CodeUnit::default();
+        // the next binders are the variables right after the last one
+        let Body::Abs(last, _) = *dump(&mut cu, "fn y => y").body else {
+            unreachable!()
+        };
+        let frees: Vec<_> = (last + 1..last + 100)
+            .map(|v| cu.scope.get_alias(v))
+            .filter(|name| !["y", "fn", "in", "let", "use"].contains(&name.as_str()))
+            .collect();
+        let t = dump(&mut cu, &format!("fn y => {}", frees.join(" ")));
+        assert_eq!(t.free_vars().len(), frees.len());
+    }
+}
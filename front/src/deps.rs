@@ -0,0 +1,114 @@
+//! Dependencies between the definitions of a program
+
+use std::collections::HashMap;
+
+use crate::Ast;
+
+struct Tarjan {
+    edges: Vec<Vec<usize>>,
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next: usize,
+    groups: Vec<Vec<usize>>,
+}
+
+impl Tarjan {
+    fn visit(&mut self, v: usize) {
+        self.index[v] = Some(self.next);
+        self.low[v] = self.next;
+        self.next += 1;
+        self.stack.push(v);
+        self.on_stack[v] = true;
+        for i in 0..self.edges[v].len() {
+            let w = self.edges[v][i];
+            match self.index[w] {
+                None => {
+                    self.visit(w);
+                    self.low[v] = self.low[v].min(self.low[w]);
+                }
+                Some(idx) if self.on_stack[w] => self.low[v] = self.low[v].min(idx),
+                Some(_) => (),
+            }
+        }
+        if Some(self.low[v]) == self.index[v] {
+            let mut group = Vec::new();
+            while let Some(w) = self.stack.pop() {
+                self.on_stack[w] = false;
+                group.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            group.sort();
+            self.groups.push(group);
+        }
+    }
+}
+
+/// Groups the definitions that refer to each other (the strongly connected
+/// components of the dependency graph), each group is before the ones that
+/// depend on it. Definitions are referred by their index in `defs`.
+pub fn groups(defs: &[(&str, &Ast)]) -> Vec<Vec<usize>> {
+    let mut idxs = HashMap::new();
+    for (i, (name, _)) in defs.iter().enumerate() {
+        idxs.entry(*name).or_insert(i);
+    }
+    let edges = defs
+        .iter()
+        .map(|(_, t)| {
+            let mut deps: Vec<_> = t
+                .free_names()
+                .iter()
+                .filter_map(|n| idxs.get(n))
+                .copied()
+                .collect();
+            deps.sort();
+            deps
+        })
+        .collect();
+    let mut tarjan = Tarjan {
+        edges,
+        index: vec![None; defs.len()],
+        low: vec![0; defs.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; defs.len()],
+        next: 0,
+        groups: Vec::new(),
+    };
+    for v in 0..defs.len() {
+        if tarjan.index[v].is_none() {
+            tarjan.visit(v);
+        }
+    }
+    tarjan.groups
+}
+
+/// The name applied at the head of the term, out of any abstraction
+fn head(t: &Ast) -> Option<&str> {
+    match t {
+        Ast::Var(v) => Some(v),
+        Ast::App(m, _) => head(m),
+        _ => None,
+    }
+}
+
+/// Finds definitions of `group` that unfold to themselves at their heads, like
+/// `A = B x` and `B = A`, as names from the first definition to itself again
+pub fn head_cycle(group: &[(&str, &Ast)]) -> Option<Vec<String>> {
+    let def = |name: &str| group.iter().find(|(n, _)| *n == name).map(|(_, t)| *t);
+    for (name, t) in group {
+        let mut chain = vec![*name];
+        let mut t = *t;
+        while let Some(next) = head(t).filter(|n| def(n).is_some()) {
+            if let Some(start) = chain.iter().position(|n| *n == next) {
+                chain.push(next);
+                return Some(chain[start..].iter().map(|n| n.to_string()).collect());
+            }
+            chain.push(next);
+            t = def(next).unwrap();
+        }
+    }
+    None
+}
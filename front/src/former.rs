@@ -15,12 +15,22 @@ where
     pub fn set(&mut self) {
         while let Some(tk) = self.it.next() {
             match tk.1 {
-                Token::Tab | Token::NewLine
-                    if matches!(self.it.peek(), Some((_, Token::NewLine | Token::Tab, _))) =>
+                Token::Tab => continue,
+                Token::Indent
+                    if !matches!(
+                        self.it.peek(),
+                        None | Some((_, Token::NewLine | Token::Indent, _))
+                    ) =>
                 {
                     continue
                 }
-                Token::NewLine => self.finish_all(),
+                // a line with just whitespace is a blank line too
+                Token::NewLine | Token::Indent => {
+                    self.finish_all();
+                    if !matches!(self.buf.last(), None | Some((_, Token::NewLine, _))) {
+                        self.buf.push((tk.0, Token::NewLine, tk.2));
+                    }
+                }
                 Token::LetKw => {
                     self.buf.push(tk);
                     self.stack.push(Implicit::Let)
@@ -61,7 +71,7 @@ where
     }
 
     pub fn finish_all(&mut self) {
-        while let Some(_) = self.stack.pop() {
+        while self.stack.pop().is_some() {
             self.push_meta(Token::CloseParen);
         }
     }
@@ -1,7 +1,7 @@
-use std::path::PathBuf;
+use std::{collections::HashSet, path::PathBuf};
 
 use lalrpop_util::lalrpop_mod;
-lalrpop_mod!(pub grammar);
+lalrpop_mod!(#[allow(clippy::all)] pub grammar);
 
 #[derive(Debug)]
 pub enum Ast {
@@ -23,11 +23,55 @@ pub enum Op {
     Access,
 }
 
+impl Ast {
+    /// Names that the expression uses without binding them
+    pub fn free_names(&self) -> HashSet<&str> {
+        let mut frees = HashSet::new();
+        self.free_names_from(&mut Vec::new(), &mut frees);
+        frees
+    }
+
+    fn free_names_from<'a>(&'a self, bound: &mut Vec<&'a str>, frees: &mut HashSet<&'a str>) {
+        match self {
+            Self::Var(v) => {
+                if !bound.contains(&v.as_str()) {
+                    frees.insert(v);
+                }
+            }
+            Self::App(m, n) => {
+                m.free_names_from(bound, frees);
+                n.free_names_from(bound, frees);
+            }
+            Self::Abs(v, m) => {
+                bound.push(v);
+                m.free_names_from(bound, frees);
+                bound.pop();
+            }
+            // each binding sees the previous ones
+            Self::Let(defs, m) => {
+                let depth = bound.len();
+                for def in defs {
+                    if let Self::Assign(id, def) = def {
+                        def.free_names_from(bound, frees);
+                        bound.push(id);
+                    }
+                }
+                m.free_names_from(bound, frees);
+                bound.truncate(depth);
+            }
+            Self::BinOp(l, Op::Access, _) => l.free_names_from(bound, frees),
+            Self::Assign(_, m) => m.free_names_from(bound, frees),
+            Self::Program(..) | Self::Use(..) => (),
+        }
+    }
+}
+
 use church::Term;
 use thiserror::Error;
 
 pub mod compiler;
 pub mod cu;
+pub mod deps;
 pub mod former;
 pub mod parser;
 
@@ -36,6 +80,12 @@ pub enum Error {
     #[error("couldn't find module {0}")]
     ModuleNotFound(PathBuf),
 
+    #[error("{0} isn't a valid BLC file: {1}")]
+    InvalidBlc(PathBuf, church::blc::BlcError),
+
+    #[error("{} use each other", .0.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(" -> "))]
+    UseCycle(Vec<PathBuf>),
+
     #[error("Definition for `{0}` wasn't found")]
     DefNotFound(String),
 
@@ -45,8 +95,14 @@ pub enum Error {
     #[error("{0:?}")]
     LexerError(()),
 
+    #[error("`{}` unfolds to itself before reaching an abstraction, so it can't be compiled", .0.join(" -> "))]
+    HeadCycle(Vec<String>),
+
     #[error("Variable {0}'ve been already deifned as {1}")]
     AlreadyDefined(String, Term),
+
+    #[error("a program is made of definitions and `use`s, not {0}")]
+    NotADefinition(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
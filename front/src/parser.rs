@@ -29,6 +29,10 @@ pub enum Token {
     #[token("\n")]
     NewLine,
 
+    /// A line break followed by indentation, that continues the line
+    #[regex(r"\n[ \t]+")]
+    Indent,
+
     #[token("\t")]
     Tab,
 
@@ -60,6 +64,7 @@ impl Token {
                 Self::Lambda => buf.push_str("fn"),
                 Self::Tab => buf.push('\t'),
                 Self::NewLine => buf.push('\n'),
+                Self::Indent => buf.push_str("\n\t"),
                 Self::LetKw => buf.push_str("let"),
                 Self::InKw => buf.push_str("in"),
                 Self::UseKw => buf.push_str("use"),
@@ -113,4 +118,9 @@ mod tests {
         assert_alpha_eq("a b (c d) e f", "(((a b) (c d)) e) f");
         assert_alpha_ne("a b (c d) e f", "a (((b (c d)) e) f)");
     }
+
+    #[test]
+    fn scoped_let() {
+        assert_alpha_eq("fn x => (let y = x in (y))", "fn x => ((fn y => y) x)");
+    }
 }
@@ -98,7 +98,7 @@ mod tests {
     fn assert_eq_args(s: &str, subs: Option<&[&str]>) {
         assert_eq!(
             get_args(s),
-            subs.map(|s| s.into_iter().map(ToString::to_string).collect::<Vec<_>>())
+            subs.map(|s| s.iter().map(ToString::to_string).collect::<Vec<_>>())
         );
     }
 
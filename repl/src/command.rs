@@ -1,3 +1,10 @@
+use church::{
+    alias::Naming,
+    ski::{Abstraction, Comb},
+    Term, Trace, TraceStep,
+};
+use std::fs;
+
 #[derive(Clone)]
 pub struct Command {
     pub name: &'static str,
@@ -16,8 +23,16 @@ const SHOW_CMD: Command = Command {
             //     println!("let {name} = {}", obj.with(|o| o.to_pretty_class_str(0)))
             // }),
             "env" => std::env::vars().for_each(|(k, v)| println!("{k:?} = {v:?}")),
+            "orders" => {
+                church::strategy::STRATEGIES
+                    .iter()
+                    .for_each(|s| println!("{}", s.name()));
+                ["call-by-value", "call-by-need", "krivine", "cek", "nbe"]
+                    .iter()
+                    .for_each(|s| println!("{s}"));
+            }
             _ if input.len() == 1 && r.cu.scope.defs.contains_key(&input[0]) => {
-                println!("{}", r.cu.scope.defs[&input[0]]);
+                println!("{}", r.cu.scope.definition(&input[0]).unwrap());
             }
             _ => eprintln!("what is {input:?}?"),
         }
@@ -79,7 +94,20 @@ const SET_CMD: Command = Command {
             "eval" => set_arg(&mut r.settings.eval, &input[1])?,
             "prettify" => set_arg(&mut r.settings.prettify, &input[1])?,
             "order" => set_arg(&mut r.settings.b_order, &input[1])?,
+            "eta" => set_arg(&mut r.settings.eta, &input[1])?,
             "show_form" => set_arg(&mut r.settings.show_form, &input[1])?,
+            "max_steps" => set_arg(&mut r.settings.max_steps, &input[1])?,
+            "max_size" => set_arg(&mut r.settings.max_size, &input[1])?,
+            "detect_loops" => set_arg(&mut r.settings.detect_loops, &input[1])?,
+            "trace" => set_arg(&mut r.settings.trace, &input[1])?,
+            "ascii" => {
+                set_arg(&mut r.settings.ascii, &input[1])?;
+                r.cu.scope.naming = if r.settings.ascii {
+                    Naming::Ascii
+                } else {
+                    Naming::Greek
+                };
+            }
             _ => Err(crate::Err::UnknownSetting(input[0].clone()))?,
         };
         Ok(())
@@ -91,6 +119,139 @@ const SET_CMD: Command = Command {
     help: "sets something of the repl",
 };
 
+const TERM_CMD: Command = Command {
+    name: "term",
+    cmd: |r, input| {
+        let t = r.dump_expr(&input[0])?;
+        r.print_term(&t);
+        r.show_redexes(&t);
+        r.trace = r.settings.trace.then(|| Trace::new(t.clone()));
+        r.term = Some(t);
+        Ok(())
+    },
+    args: &[("expr", "expression to work on, without reducing it")],
+    help: "sets the term explored by `:redexes` and `:contract`",
+};
+
+const REDEXES_CMD: Command = Command {
+    name: "redexes",
+    cmd: |r, _| {
+        let t = r.term.as_ref().ok_or(crate::Error::NoTerm)?;
+        r.show_redexes(t);
+        Ok(())
+    },
+    args: &[],
+    help: "lists the redexes of the last term, with their positions",
+};
+
+const CONTRACT_CMD: Command = Command {
+    name: "contract",
+    cmd: |r, input| {
+        let idx: usize = input[0].parse()?;
+        let mut t = r.term.take().ok_or(crate::Error::NoTerm)?;
+        let path = t.redexes().into_iter().nth(idx);
+        let res = path.ok_or(crate::Error::UnknownRedex(idx));
+        if let Ok(p) = &res {
+            let (redex, size) = (t.subterm(p).unwrap().clone(), t.size());
+            t.contract_at(p);
+            if let Some(trace) = &mut r.trace {
+                let contractum = t.subterm(p).unwrap().clone();
+                let step = TraceStep::new("manual", p.clone(), redex, contractum, size);
+                trace.steps.push(step);
+            }
+            r.print_term(&t);
+            r.show_redexes(&t);
+        }
+        r.term = Some(t);
+        res?;
+        Ok(())
+    },
+    args: &[("redex", "number of the redex, as listed by `:redexes`")],
+    help: "contracts a chosen redex of the last term",
+};
+
+const TRACE_CMD: Command = Command {
+    name: "trace",
+    cmd: |r, input| {
+        let trace = r.trace.as_ref().ok_or(crate::Error::NoTrace)?;
+        match input[0].as_str() {
+            "save" => fs::write(&input[1], trace.to_json())?,
+            action => Err(crate::Error::UnknownTraceAction(action.to_owned()))?,
+        }
+        Ok(())
+    },
+    args: &[
+        ("action", "what to do with the trace, just `save` for now"),
+        ("file", "path of the JSON file"),
+    ],
+    help: "saves the steps of the last reduction, with their positions and sizes, as JSON",
+};
+
+const SKI_CMD: Command = Command {
+    name: "ski",
+    cmd: |r, input| {
+        let abstraction = match input[0].as_str() {
+            "naive" => Abstraction::Naive,
+            "turner" => Abstraction::Turner,
+            a => Err(crate::Error::UnknownAbstraction(a.to_owned()))?,
+        };
+        let t = r.dump_expr(&input[1])?;
+        let mut c = Comb::from_term(&t, abstraction);
+        println!("{c}");
+        let res = r.reduce(|_, fuel| c.normalize(fuel));
+        r.report(res);
+        println!("{c}");
+        let t = Term::from(&c);
+        r.print_term(&t);
+        r.term = Some(t);
+        r.trace = None;
+        Ok(())
+    },
+    args: &[
+        (
+            "abstraction",
+            "`naive` for just S, K and I, or `turner` for B and C too",
+        ),
+        ("expr", "expression to translate"),
+    ],
+    help: "translates an expression to combinators, reduces it weakly and translates it back",
+};
+
+const BLC_CMD: Command = Command {
+    name: "blc",
+    cmd: |r, input| {
+        let t = r.dump_expr(&input[0])?;
+        let bits = t.to_blc_bits()?;
+        println!("{bits}");
+        println!(
+            "{} bits, {} bytes packed",
+            bits.len(),
+            bits.len().div_ceil(8)
+        );
+        Ok(())
+    },
+    args: &[("expr", "closed expression to encode")],
+    help: "shows the binary lambda calculus encoding of an expression and its size",
+};
+
+const STATS_CMD: Command = Command {
+    name: "stats",
+    cmd: |r, input| {
+        let t = r.dump_expr(&input[0])?.inline_refs();
+        println!("size: {}", t.size());
+        println!("depth: {}", t.depth());
+        println!("variables: {}", t.var_count());
+        println!("applications: {}", t.app_count());
+        println!("abstractions: {}", t.abs_count());
+        println!("redexes: {}", t.redex_count());
+        println!("binder nesting: {}", t.binder_nesting());
+        println!("closed: {}", t.is_closed());
+        Ok(())
+    },
+    args: &[("expr", "expression to measure")],
+    help: "shows the size, depth, node counts, redexes and binder nesting of an expression, with its definitions inlined",
+};
+
 const CMDS_CMD: Command = Command {
     name: "cmds",
     cmd: |r, _| {
@@ -116,4 +277,18 @@ const QUIT_CMD: Command = Command {
     help: "quits the repl",
 };
 
-pub const COMMANDS: &[Command] = &[SHOW_CMD, HELP_CMD, ENV_CMD, SET_CMD, CMDS_CMD, QUIT_CMD];
+pub const COMMANDS: &[Command] = &[
+    SHOW_CMD,
+    HELP_CMD,
+    ENV_CMD,
+    SET_CMD,
+    TERM_CMD,
+    REDEXES_CMD,
+    CONTRACT_CMD,
+    TRACE_CMD,
+    SKI_CMD,
+    BLC_CMD,
+    STATS_CMD,
+    CMDS_CMD,
+    QUIT_CMD,
+];
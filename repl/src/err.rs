@@ -2,6 +2,26 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum Error {
-    #[error("there isn't a defined strategy for beta reduction with this name")]
+    #[error(
+        "there isn't a defined strategy for beta reduction with this name, see `:show orders`"
+    )]
     UnknownBetaOrder,
+
+    #[error("there isn't a term to work on, evaluate an expression or use `:term`")]
+    NoTerm,
+
+    #[error("the term doesn't have a redex numbered {0}, see `:redexes`")]
+    UnknownRedex(usize),
+
+    #[error(
+        "there isn't a trace of the last term, it's only recorded by step-by-step orders \
+         after `:set trace true`"
+    )]
+    NoTrace,
+
+    #[error("unknown trace action `{0}`, the only one is `save`")]
+    UnknownTraceAction(String),
+
+    #[error("unknown bracket abstraction `{0}`, use `naive` or `turner`")]
+    UnknownAbstraction(String),
 }
@@ -1,4 +1,7 @@
-use church::{Body, Term};
+use church::{
+    strategy::{Eta, WithEta},
+    Body, Fuel, Normalization, Outcome, Path, ReductionStrategy, Ref, Term, Trace,
+};
 use color_eyre::eyre::Result;
 use command::Command;
 use front::{
@@ -7,8 +10,16 @@ use front::{
     Ast,
 };
 use rustyline::{error::ReadlineError, DefaultEditor};
-use settings::Settings;
-use std::{collections::HashMap, time::Instant};
+use settings::{BetaOrder, Settings};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+    time::Instant,
+};
 
 pub use args::Err;
 
@@ -18,12 +29,46 @@ pub mod err;
 pub mod settings;
 pub use err::Error;
 
+/// Set by Ctrl-C, interrupts the running reduction
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// If a reduction is running, otherwise Ctrl-C exits as usual
+static REDUCING: AtomicBool = AtomicBool::new(false);
+
+/// Keeps the reference unfolded by the last step, as the term is printed the
+/// same before and after it
+struct Unfolding<'a> {
+    strategy: &'a dyn ReductionStrategy,
+    unfolded: RefCell<Option<Ref>>,
+}
+
+impl ReductionStrategy for Unfolding<'_> {
+    fn name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        self.strategy.next_redex(t)
+    }
+
+    fn step(&self, t: &mut Term) -> bool {
+        let unfolded = self
+            .next_redex(t)
+            .and_then(|p| t.subterm(&p)?.unfolds().cloned());
+        *self.unfolded.borrow_mut() = unfolded;
+        self.strategy.step(t)
+    }
+}
+
 pub struct Repl {
     pub cu: CodeUnit,
     pub rl: DefaultEditor,
     pub settings: Settings,
     pub commands: HashMap<String, Command>,
     pub should_exit: bool,
+    /// Last evaluated term, the one explored by `:redexes` and `:contract`
+    pub term: Option<Term>,
+    /// Steps that led to `term`, saved by `:trace`
+    pub trace: Option<Trace>,
 }
 
 impl Default for Repl {
@@ -38,6 +83,8 @@ impl Default for Repl {
                 .map(|c| (c.name.to_owned(), c))
                 .collect(),
             should_exit: false,
+            term: None,
+            trace: None,
         }
     }
 }
@@ -87,7 +134,7 @@ impl Repl {
             front::grammar::ExprParser::new()
                 .parse(tks)
                 .map_err(front::Error::ParserError)
-            } else {
+        } else {
             self.cu
                 .program_parser
                 .parse(tks)
@@ -98,7 +145,7 @@ impl Repl {
         }
         if self.settings.eval {
             if is_expr {
-                self.reduce_expr(&ast);
+                self.reduce_expr(&ast)?;
             } else {
                 self.cu.eval(ast)?;
             }
@@ -134,21 +181,194 @@ impl Repl {
         }
     }
 
-    fn reduce_expr(&mut self, ut: &Ast) {
-        let mut t = self.cu.scope.dump(ut).unwrap();
+    fn reduce_expr(&mut self, ut: &Ast) -> Result<()> {
+        let mut t = self.cu.scope.dump(ut)?;
         println!("{t}");
-        while !self.redex_step(&mut t) {
-            self.print_term(&t);
+        let bench = self.settings.bench;
+        if bench && self.settings.trace {
+            eprintln!("warn: traces aren't recorded while benchmarking");
+        }
+        // the biggest of the intermediate terms, that the at-once evaluators don't have
+        let mut peak = t.size();
+        let (res, trace) = self.reduce(|r, fuel| {
+            r.bench("reduction", |r| {
+                // printing would be timed too, so just the normal form is printed after
+                let mut inspect = |t: &Term| {
+                    if bench {
+                        peak = peak.max(t.size());
+                    } else {
+                        r.print_term(t)
+                    }
+                };
+                match r.settings.b_order {
+                    BetaOrder::Step(s) if r.settings.eta => {
+                        r.normalize_step(&mut t, &WithEta(s), fuel, inspect)
+                    }
+                    BetaOrder::Step(s) => r.normalize_step(&mut t, s, fuel, inspect),
+                    BetaOrder::CallByNeed => (
+                        r.normalize_at_once(&mut t, fuel, Term::normalize_by_need, &mut inspect),
+                        None,
+                    ),
+                    BetaOrder::Krivine => (
+                        r.normalize_at_once(&mut t, fuel, Term::normalize_krivine, &mut inspect),
+                        None,
+                    ),
+                    BetaOrder::Cek => (
+                        r.normalize_at_once(&mut t, fuel, Term::normalize_cek, &mut inspect),
+                        None,
+                    ),
+                    BetaOrder::Nbe => (
+                        r.normalize_at_once(&mut t, fuel, Term::normalize_nbe, &mut inspect),
+                        None,
+                    ),
+                }
+            })
+        });
+        if bench {
+            if res.outcome == Outcome::Normal {
+                self.print_term(&t);
+            }
+            match self.settings.b_order {
+                BetaOrder::Step(_) => println!("steps: {}, peak size: {peak}", res.steps),
+                _ => println!("steps: {}, peak size: N/A", res.steps),
+            }
+        }
+        self.report(res);
+        if res.outcome == Outcome::Interrupted {
+            if let BetaOrder::Step(_) = self.settings.b_order {
+                eprintln!("the last term reached:");
+                self.print_term(&t);
+            } else {
+                eprintln!("this order has no intermediate terms, so the last one isn't available");
+            }
+        }
+        self.term = Some(t);
+        self.trace = trace;
+        Ok(())
+    }
+
+    /// The budget of a reduction, as in the settings
+    pub fn fuel(&self) -> Fuel {
+        let mut fuel = Fuel::steps(self.settings.max_steps).with_size(self.settings.max_size);
+        if self.settings.detect_loops {
+            fuel = fuel.detecting_loops();
+        }
+        fuel.interruptible(&INTERRUPTED)
+    }
+
+    /// Runs a reduction with the budget of the settings, Ctrl-C interrupts
+    /// it just while it's running
+    pub fn reduce<T>(&mut self, f: impl FnOnce(&mut Self, Fuel) -> T) -> T {
+        static HANDLER: Once = Once::new();
+        HANDLER.call_once(|| {
+            // At the prompt, Ctrl-C is read by rustyline instead
+            let handler = || {
+                if REDUCING.load(Ordering::Relaxed) {
+                    INTERRUPTED.store(true, Ordering::Relaxed);
+                } else {
+                    std::process::exit(130);
+                }
+            };
+            if let Err(e) = ctrlc::set_handler(handler) {
+                eprintln!("warn: reductions can't be interrupted: {e}");
+            }
+        });
+        INTERRUPTED.store(false, Ordering::Relaxed);
+        REDUCING.store(true, Ordering::Relaxed);
+        let v = f(self, self.fuel());
+        REDUCING.store(false, Ordering::Relaxed);
+        v
+    }
+
+    /// Explains why a reduction has stopped, when it's not on its normal form
+    pub fn report(&self, res: Normalization) {
+        match res.outcome {
+            Outcome::Normal => {}
+            Outcome::OutOfFuel => eprintln!("gave up after {} steps: out of fuel", res.steps),
+            Outcome::SizeExceeded => eprintln!(
+                "gave up after {} steps: term got bigger than {} nodes",
+                res.steps, self.settings.max_size
+            ),
+            Outcome::Cycle { start: 0, period } => {
+                eprintln!("loops: this term reduces to itself after {period} steps")
+            }
+            Outcome::Cycle { start, period } => {
+                eprintln!("loops: the term of step {start} reduces to itself after {period} steps")
+            }
+            Outcome::Interrupted => eprintln!("interrupted after {} steps", res.steps),
+            Outcome::Diverges => eprintln!(
+                "diverges: gave up after {} steps, the term keeps growing by reproducing a redex",
+                res.steps
+            ),
+        }
+    }
+
+    /// Compiles an expression, without reducing it
+    pub fn dump_expr(&mut self, src: &str) -> Result<Term> {
+        let tks = self.get_tokens(src)?;
+        let ast = front::grammar::ExprParser::new()
+            .parse(tks)
+            .map_err(front::Error::ParserError)?;
+        Ok(self.cu.scope.dump(&ast)?)
+    }
+
+    pub fn show_redexes(&self, t: &Term) {
+        for (i, path) in t.redexes().iter().enumerate() {
+            println!("{i}: {path} {}", t.subterm(path).unwrap());
         }
     }
 
+    /// Runs a step-by-step order, recording its trace when tracing is on
+    fn normalize_step(
+        &self,
+        t: &mut Term,
+        strategy: &dyn ReductionStrategy,
+        fuel: Fuel,
+        mut inspect: impl FnMut(&Term),
+    ) -> (Normalization, Option<Trace>) {
+        let bench = self.settings.bench;
+        let strategy = Unfolding {
+            strategy,
+            unfolded: RefCell::default(),
+        };
+        // an unfolding is shown by the name of the reference
+        let inspect = |t: &Term| match strategy.unfolded.take() {
+            Some(r) if !bench => println!("unfold {r}"),
+            _ => inspect(t),
+        };
+        if self.settings.trace && !bench {
+            let (res, trace) = t.normalize_traced(&strategy, fuel, inspect);
+            (res, Some(trace))
+        } else {
+            (t.normalize_with(&strategy, fuel, inspect), None)
+        }
+    }
+
+    /// Runs an evaluator that doesn't have intermediate terms, inspecting just the normal form
+    fn normalize_at_once(
+        &self,
+        t: &mut Term,
+        fuel: Fuel,
+        f: impl Fn(&mut Term, Fuel) -> Normalization,
+        inspect: &mut impl FnMut(&Term),
+    ) -> Normalization {
+        let mut res = f(t, fuel);
+        if res.outcome == Outcome::Normal {
+            if self.settings.eta {
+                res.steps += t.normalize(&Eta, Fuel::UNBOUNDED).steps;
+            }
+            inspect(t);
+        }
+        res
+    }
+
     // Looks like a shitty function, but as the language evolves, it's going to be worth
-    fn needs_program_parser<'a>(tokens: &[ParserToken]) -> bool {
+    fn needs_program_parser(tokens: &[ParserToken]) -> bool {
         tokens[0].1 == Token::UseKw
             || (tokens[0].1 != Token::LetKw && tokens.iter().any(|t| t.1 == Token::Assign))
     }
 
-    pub fn print_term(&mut self, t: &Term) {
+    pub fn print_term(&self, t: &Term) {
         if self.settings.prettify {
             println!("{}", self.cu.scope.pretty_show(t));
         } else {
@@ -156,13 +376,6 @@ impl Repl {
         }
     }
 
-    fn redex_step(&self, p: &mut Term) -> bool {
-        match self.settings.b_order {
-            settings::BetaOrder::Normal => p.normal_beta_redex_step(),
-            settings::BetaOrder::CallByValue => p.cbv_beta_redex_step(),
-        }
-    }
-
     pub fn show_ast(p: &Term, depth: usize) {
         let tab = "\t".repeat(depth);
         print!("{tab}");
@@ -177,6 +390,7 @@ impl Repl {
                 println!("abs {v}:");
                 Self::show_ast(m, depth + 1);
             }
+            Body::Ref(r) => println!("ref {}", r.name()),
         }
     }
 
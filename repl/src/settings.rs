@@ -1,3 +1,4 @@
+use church::{strategy, ReductionStrategy};
 use std::str::FromStr;
 
 pub struct Settings {
@@ -10,6 +11,14 @@ pub struct Settings {
     pub show_output: bool,
     pub prettify: bool,
     pub b_order: BetaOrder,
+    pub eta: bool,
+    pub max_steps: usize,
+    pub max_size: usize,
+    pub detect_loops: bool,
+    /// Records the steps of the reductions, for `:trace`
+    pub trace: bool,
+    /// Shows variables with just ASCII names
+    pub ascii: bool,
 }
 
 impl Default for Settings {
@@ -24,15 +33,28 @@ impl Default for Settings {
             prettify: true,
             show_form: false,
             b_order: BetaOrder::default(),
+            eta: false,
+            max_steps: 10_000,
+            max_size: 1_000_000,
+            detect_loops: true,
+            trace: false,
+            ascii: false,
         }
     }
 }
 
-#[derive(Default)]
 pub enum BetaOrder {
-    #[default]
-    Normal,
-    CallByValue,
+    Step(&'static dyn ReductionStrategy),
+    CallByNeed,
+    Krivine,
+    Cek,
+    Nbe,
+}
+
+impl Default for BetaOrder {
+    fn default() -> Self {
+        Self::Step(&strategy::Normal)
+    }
 }
 
 impl FromStr for BetaOrder {
@@ -40,9 +62,15 @@ impl FromStr for BetaOrder {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "normal" => Ok(Self::Normal),
-            "call-by-value" => Ok(Self::CallByValue),
-            _ => Err(crate::Error::UnknownBetaOrder),
+            // the strong call-by-value order, named so before the weak one existed
+            "call-by-value" => Ok(Self::Step(&strategy::Applicative)),
+            "call-by-need" => Ok(Self::CallByNeed),
+            "krivine" => Ok(Self::Krivine),
+            "cek" => Ok(Self::Cek),
+            "nbe" => Ok(Self::Nbe),
+            _ => strategy::by_name(s)
+                .map(Self::Step)
+                .ok_or(crate::Error::UnknownBetaOrder),
         }
     }
 }
@@ -0,0 +1,108 @@
+use std::fmt;
+
+/// Greek letters first, as the Latin ones are usually taken by definitions
+const GREEK: &[char] = &[
+    'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ', 'σ', 'ς', 'τ',
+    'υ', 'φ', 'χ', 'ψ', 'ω', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n',
+    'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1', '2', '3', '4', '5', '6',
+    '7', '8', '9',
+];
+
+const ASCII: &[char] = &[
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9',
+];
+
+/// How variables are named from their indices. Names are the digits of the
+/// index in the base of the alphabet, from the least significant one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Naming {
+    /// The index itself, like `λ0.0`
+    Numeric,
+    /// Greek and then Latin letters, like `λα.α`
+    #[default]
+    Greek,
+    /// Just Latin letters and digits, like `λa.a`
+    Ascii,
+}
+
+impl Naming {
+    /// The naming of `Term`'s `Display`, chosen by the `aliased-vars` and
+    /// `ascii-vars` features
+    pub const DISPLAY: Self = if cfg!(feature = "ascii-vars") {
+        Self::Ascii
+    } else if cfg!(feature = "aliased-vars") {
+        Self::Greek
+    } else {
+        Self::Numeric
+    };
+
+    fn alphabet(self) -> &'static [char] {
+        match self {
+            Self::Numeric => &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'],
+            Self::Greek => GREEK,
+            Self::Ascii => ASCII,
+        }
+    }
+
+    /// Checks if `c` can be part of a name
+    pub fn is_name_char(self, c: char) -> bool {
+        self.alphabet().contains(&c)
+    }
+
+    pub fn write(self, var: usize, f: &mut impl fmt::Write) -> fmt::Result {
+        if self == Self::Numeric {
+            return write!(f, "{var}");
+        }
+        let alphabet = self.alphabet();
+        let mut counter = var;
+        loop {
+            f.write_char(alphabet[counter % alphabet.len()])?;
+            counter /= alphabet.len();
+            if counter == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn name(self, var: usize) -> String {
+        let mut s = String::new();
+        self.write(var, &mut s).unwrap();
+        s
+    }
+
+    /// The index named by `name`, if it's a valid one
+    pub fn index(self, name: &str) -> Option<usize> {
+        if self == Self::Numeric {
+            return name.parse().ok();
+        }
+        let alphabet = self.alphabet();
+        if name.is_empty() {
+            return None;
+        }
+        name.chars().rev().try_fold(0usize, |counter, c| {
+            let digit = alphabet.iter().position(|a| *a == c)?;
+            counter.checked_mul(alphabet.len())?.checked_add(digit)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Naming;
+
+    #[test]
+    fn names_are_read_back() {
+        for naming in [Naming::Numeric, Naming::Greek, Naming::Ascii] {
+            for var in (0..200).chain([3_600, 12_345, usize::MAX]) {
+                let name = naming.name(var);
+                assert!(name.chars().all(|c| naming.is_name_char(c)));
+                assert_eq!(naming.index(&name), Some(var), "{naming:?} {name}");
+            }
+        }
+        assert_eq!(Naming::Greek.name(0), "α");
+        assert_eq!(Naming::Greek.name(60), "αβ");
+        assert!(Naming::Ascii.name(12_345).is_ascii());
+        assert_eq!(Naming::Ascii.index("λ"), None);
+    }
+}
@@ -0,0 +1,377 @@
+//! A self-describing binary format for terms. After the `MAGIC` header come
+//! the `VERSION` and a flags byte, then the nodes, with variables as LEB128
+//! varints. Terms are written as a tree in pre-order, or when shared, as a
+//! table of distinct subterms in post-order, whose entries refer to the
+//! previous ones, with the root as the last entry.
+
+use std::{collections::HashMap, error::Error, fmt};
+
+use crate::{Body, Term};
+
+pub const MAGIC: [u8; 4] = *b"CHRC";
+pub const VERSION: u8 = 1;
+
+/// Most nodes that a decoded term can have, as a table of shared subterms can
+/// describe a term exponentially bigger than its bytes
+pub const MAX_NODES: usize = 1 << 24;
+/// Deepest that a decoded term can be, as terms are handled recursively
+pub const MAX_DEPTH: usize = 2048;
+
+/// The nodes are a table of distinct subterms
+const SHARED: u8 = 1;
+
+const VAR: u8 = 0;
+const APP: u8 = 1;
+const ABS: u8 = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownFlags(u8),
+    UnexpectedEnd,
+    UnknownTag(u8),
+    VarintOverflow,
+    /// A shared entry refers to one that isn't before it
+    BadRef(usize),
+    TrailingBytes,
+    /// The term has more than `MAX_NODES` nodes
+    TooBig,
+    /// The term is deeper than `MAX_DEPTH`
+    TooDeep,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMagic => f.write_str("not an encoded term, the header is wrong"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+            Self::UnknownFlags(fl) => write!(f, "unknown flags {fl:#010b}"),
+            Self::UnexpectedEnd => f.write_str("unexpected end of the bytes"),
+            Self::UnknownTag(t) => write!(f, "unknown node tag {t}"),
+            Self::VarintOverflow => f.write_str("varint doesn't fit in an usize"),
+            Self::BadRef(r) => write!(f, "reference to the entry {r}, that isn't before it"),
+            Self::TrailingBytes => f.write_str("bytes after the end of the term"),
+            Self::TooBig => write!(f, "the term has more than {MAX_NODES} nodes"),
+            Self::TooDeep => write!(f, "the term is deeper than {MAX_DEPTH} nodes"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+fn write_varint(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// A node of the table, whose subterms are indices of previous entries
+enum Entry {
+    Var(usize),
+    App(usize, usize),
+    Abs(usize, usize),
+}
+
+/// Builds the term of the `i`-th entry of the table
+fn build(entries: &[Entry], i: usize) -> Term {
+    match entries[i] {
+        Entry::Var(v) => Body::Var(v),
+        Entry::App(m, n) => Body::App(build(entries, m), build(entries, n)),
+        Entry::Abs(v, m) => Body::Abs(v, build(entries, m)),
+    }
+    .into()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let (b, rest) = self.bytes.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(*b)
+    }
+
+    fn varint(&mut self) -> Result<usize, DecodeError> {
+        let mut n = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let b = self.byte()?;
+            let bits = (b & 0x7f) as usize;
+            if bits << shift >> shift != bits {
+                return Err(DecodeError::VarintOverflow);
+            }
+            n |= bits << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(DecodeError::VarintOverflow)
+    }
+
+    /// Reads a term under `depth` nodes, its size is bounded by the bytes
+    fn tree(&mut self, depth: usize) -> Result<Term, DecodeError> {
+        if depth == MAX_DEPTH {
+            return Err(DecodeError::TooDeep);
+        }
+        Ok(match self.byte()? {
+            VAR => Body::Var(self.varint()?),
+            APP => Body::App(self.tree(depth + 1)?, self.tree(depth + 1)?),
+            ABS => Body::Abs(self.varint()?, self.tree(depth + 1)?),
+            tag => return Err(DecodeError::UnknownTag(tag)),
+        }
+        .into())
+    }
+
+    /// Reads the entries, checking the size and depth of their terms, and
+    /// then builds just the root
+    fn table(&mut self) -> Result<Term, DecodeError> {
+        let len = self.varint()?;
+        let mut entries = Vec::new();
+        let mut dims: Vec<(usize, usize)> = Vec::new();
+        for _ in 0..len {
+            let dim = |r: usize| dims.get(r).copied().ok_or(DecodeError::BadRef(r));
+            let (entry, size, depth) = match self.byte()? {
+                VAR => (Entry::Var(self.varint()?), 1, 1),
+                APP => {
+                    let (m, n) = (self.varint()?, self.varint()?);
+                    let ((m_size, m_depth), (n_size, n_depth)) = (dim(m)?, dim(n)?);
+                    (
+                        Entry::App(m, n),
+                        1 + m_size + n_size,
+                        1 + m_depth.max(n_depth),
+                    )
+                }
+                ABS => {
+                    let (v, m) = (self.varint()?, self.varint()?);
+                    let (m_size, m_depth) = dim(m)?;
+                    (Entry::Abs(v, m), 1 + m_size, 1 + m_depth)
+                }
+                tag => return Err(DecodeError::UnknownTag(tag)),
+            };
+            if size > MAX_NODES {
+                return Err(DecodeError::TooBig);
+            }
+            if depth > MAX_DEPTH {
+                return Err(DecodeError::TooDeep);
+            }
+            entries.push(entry);
+            dims.push((size, depth));
+        }
+        let root = entries
+            .len()
+            .checked_sub(1)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        Ok(build(&entries, root))
+    }
+}
+
+// The terms hashed here have their references inlined, and anyway a `Ref`
+// hashes just its name, which never changes
+#[allow(clippy::mutable_key_type)]
+impl Term {
+    /// Writes the term with its references inlined, see `Term::inline_refs`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Self::header(0);
+        self.inline_refs().write_tree(&mut out);
+        out
+    }
+
+    /// Same as `Term::to_bytes`, but each distinct subterm is written once
+    pub fn to_shared_bytes(&self) -> Vec<u8> {
+        let t = self.inline_refs();
+        let mut table = HashMap::new();
+        let mut entries = Vec::new();
+        t.write_entry(&mut table, &mut entries);
+        let mut out = Self::header(SHARED);
+        write_varint(&mut out, table.len());
+        out.extend(entries);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader { bytes };
+        for b in MAGIC {
+            if r.byte().map_err(|_| DecodeError::BadMagic)? != b {
+                return Err(DecodeError::BadMagic);
+            }
+        }
+        match r.byte()? {
+            VERSION => (),
+            v => return Err(DecodeError::UnsupportedVersion(v)),
+        }
+        let t = match r.byte()? {
+            0 => r.tree(0)?,
+            SHARED => r.table()?,
+            flags => return Err(DecodeError::UnknownFlags(flags)),
+        };
+        if !r.bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(t)
+    }
+
+    fn header(flags: u8) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend([VERSION, flags]);
+        out
+    }
+
+    fn write_tree(&self, out: &mut Vec<u8>) {
+        match self.body.as_ref() {
+            Body::Var(v) => {
+                out.push(VAR);
+                write_varint(out, *v);
+            }
+            Body::App(m, n) => {
+                out.push(APP);
+                m.write_tree(out);
+                n.write_tree(out);
+            }
+            Body::Abs(v, m) => {
+                out.push(ABS);
+                write_varint(out, *v);
+                m.write_tree(out);
+            }
+            Body::Ref(..) => unreachable!("references are inlined"),
+        }
+    }
+
+    /// Writes the subterms that aren't in `table` yet, returns the entry of the term
+    fn write_entry<'a>(&'a self, table: &mut HashMap<&'a Term, usize>, out: &mut Vec<u8>) -> usize {
+        if let Some(idx) = table.get(self) {
+            return *idx;
+        }
+        match self.body.as_ref() {
+            Body::Var(v) => {
+                out.push(VAR);
+                write_varint(out, *v);
+            }
+            Body::App(m, n) => {
+                let (m, n) = (m.write_entry(table, out), n.write_entry(table, out));
+                out.push(APP);
+                write_varint(out, m);
+                write_varint(out, n);
+            }
+            Body::Abs(v, m) => {
+                let m = m.write_entry(table, out);
+                out.push(ABS);
+                write_varint(out, *v);
+                write_varint(out, m);
+            }
+            Body::Ref(..) => unreachable!("references are inlined"),
+        }
+        let idx = table.len();
+        table.insert(self, idx);
+        idx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_varint, DecodeError, ABS, MAGIC, MAX_DEPTH, VAR, VERSION};
+    use crate::{
+        encoding::Encoding,
+        fixtures::{abs, app, omega, var},
+        Term,
+    };
+
+    #[test]
+    fn round_trip() {
+        let terms = [
+            var(usize::MAX),
+            omega(),
+            abs(300, app(var(300), abs(1 << 40, var(7)))),
+            Encoding::Parigot.nat(12),
+            Term::church_list((0..20).map(|_| Term::church_nat(5))),
+        ];
+        for t in terms {
+            assert_eq!(Term::from_bytes(&t.to_bytes()), Ok(t.clone()));
+            assert_eq!(Term::from_bytes(&t.to_shared_bytes()), Ok(t));
+        }
+    }
+
+    #[test]
+    fn sharing() {
+        // the same numeral applied to itself, over and over
+        let t = (0..20).fold(Term::church_nat(5), |ac, _| app(ac, Term::church_nat(5)));
+        let (tree, shared) = (t.to_bytes(), t.to_shared_bytes());
+        assert!(
+            shared.len() * 4 < tree.len(),
+            "{} {}",
+            shared.len(),
+            tree.len()
+        );
+
+        // λx . x, with the small varint of the variable
+        let id = abs(0, var(0));
+        assert_eq!(
+            id.to_bytes()[..],
+            [&MAGIC[..], &[VERSION, 0, 2, 0, 0, 0]].concat()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = abs(200, var(200)).to_bytes();
+        assert_eq!(Term::from_bytes(b"CHR"), Err(DecodeError::BadMagic));
+        assert_eq!(
+            Term::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Term::from_bytes(&[&bytes[..], &[0]].concat()),
+            Err(DecodeError::TrailingBytes)
+        );
+        let mut future = bytes.clone();
+        future[4] = VERSION + 1;
+        assert_eq!(
+            Term::from_bytes(&future),
+            Err(DecodeError::UnsupportedVersion(VERSION + 1))
+        );
+        // a table with an application of entries that don't exist
+        let bad_ref = [&MAGIC[..], &[VERSION, 1, 1, 1, 0, 0]].concat();
+        assert_eq!(Term::from_bytes(&bad_ref), Err(DecodeError::BadRef(0)));
+        let overflow = [&MAGIC[..], &[VERSION, 0, 0], &[0xff; 11]].concat();
+        assert_eq!(
+            Term::from_bytes(&overflow),
+            Err(DecodeError::VarintOverflow)
+        );
+    }
+
+    #[test]
+    fn limits() {
+        // λx.λx.…x, as a tree and as a table
+        let tree = |depth: usize| {
+            let nodes = [ABS, 0].repeat(depth - 1);
+            [&MAGIC[..], &[VERSION, 0], &nodes, &[VAR, 0]].concat()
+        };
+        let table = |depth: usize| {
+            let mut bytes = [&MAGIC[..], &[VERSION, 1]].concat();
+            write_varint(&mut bytes, depth);
+            bytes.extend([VAR, 0]);
+            for i in 0..depth - 1 {
+                bytes.extend([ABS, 0]);
+                write_varint(&mut bytes, i);
+            }
+            bytes
+        };
+        for bytes in [tree(MAX_DEPTH), table(MAX_DEPTH)] {
+            assert_eq!(Term::from_bytes(&bytes).map(|t| t.depth()), Ok(MAX_DEPTH));
+        }
+        for bytes in [tree(MAX_DEPTH + 1), table(MAX_DEPTH + 1)] {
+            assert_eq!(Term::from_bytes(&bytes), Err(DecodeError::TooDeep));
+        }
+
+        // x, then each entry is the application of the previous one to itself,
+        // doubling the size of the term
+        let mut table = [&MAGIC[..], &[VERSION, 1, 40, 0, 0]].concat();
+        for i in 0..39 {
+            table.extend([1, i, i]);
+        }
+        assert_eq!(Term::from_bytes(&table), Err(DecodeError::TooBig));
+    }
+}
@@ -0,0 +1,226 @@
+//! John Tromp's Binary Lambda Calculus. A closed term is written with its de
+//! Bruijn indices, `λM` as `00M`, `M N` as `01MN` and the index `i` (from 1)
+//! as `i` ones followed by a zero. The bits are either a string of `0`s and
+//! `1`s, or packed in bytes from the most significant bit, padding the last
+//! byte with zeros.
+
+use std::{error::Error, fmt};
+
+use crate::{binary::MAX_DEPTH, DeBruijn, Term};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlcError {
+    /// Only closed terms can be written, this is the first free variable
+    FreeVar(usize),
+    /// An index bigger than the number of binders around it
+    UnboundIndex(usize),
+    /// A character that isn't a bit nor whitespace, at this byte offset
+    BadChar(usize),
+    UnexpectedEnd,
+    TrailingBits,
+    /// The term is deeper than `binary::MAX_DEPTH`
+    TooDeep,
+}
+
+impl fmt::Display for BlcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FreeVar(v) => {
+                f.write_str("only closed terms have a BLC, but ")?;
+                crate::term::write_alias(*v, f)?;
+                f.write_str(" is free")
+            }
+            Self::UnboundIndex(i) => write!(f, "the index {i} doesn't have a binder"),
+            Self::BadChar(pos) => write!(f, "expected `0` or `1` at {pos}"),
+            Self::UnexpectedEnd => f.write_str("unexpected end of the bits"),
+            Self::TrailingBits => f.write_str("bits after the end of the term"),
+            Self::TooDeep => write!(f, "the term is deeper than {MAX_DEPTH} nodes"),
+        }
+    }
+}
+
+impl Error for BlcError {}
+
+fn encode(t: &DeBruijn, bits: &mut Vec<bool>) -> Result<(), BlcError> {
+    match t {
+        DeBruijn::Bound(i) => {
+            bits.extend((0..=*i).map(|_| true));
+            bits.push(false);
+        }
+        DeBruijn::Free(v) => return Err(BlcError::FreeVar(*v)),
+        DeBruijn::App(m, n) => {
+            bits.extend([false, true]);
+            encode(m, bits)?;
+            encode(n, bits)?;
+        }
+        DeBruijn::Abs(m) => {
+            bits.extend([false, false]);
+            encode(m, bits)?;
+        }
+        DeBruijn::Ref(..) => unreachable!("references are inlined"),
+    }
+    Ok(())
+}
+
+struct Decoder<I> {
+    bits: I,
+    /// Number of bits read so far
+    read: usize,
+}
+
+impl<I: Iterator<Item = Result<bool, BlcError>>> Decoder<I> {
+    fn bit(&mut self) -> Result<bool, BlcError> {
+        self.read += 1;
+        self.bits.next().unwrap_or(Err(BlcError::UnexpectedEnd))
+    }
+
+    /// Reads a term under `binders` abstractions and `depth` nodes
+    fn term(&mut self, binders: usize, depth: usize) -> Result<DeBruijn, BlcError> {
+        if depth == MAX_DEPTH {
+            return Err(BlcError::TooDeep);
+        }
+        if self.bit()? {
+            let mut ones = 1;
+            while self.bit()? {
+                ones += 1;
+            }
+            if ones > binders {
+                return Err(BlcError::UnboundIndex(ones));
+            }
+            Ok(DeBruijn::Bound(ones - 1))
+        } else if self.bit()? {
+            let m = self.term(binders, depth + 1)?;
+            let n = self.term(binders, depth + 1)?;
+            Ok(DeBruijn::App(m.into(), n.into()))
+        } else {
+            Ok(DeBruijn::Abs(self.term(binders + 1, depth + 1)?.into()))
+        }
+    }
+}
+
+impl Term {
+    pub fn to_blc_bits(&self) -> Result<String, BlcError> {
+        let mut bits = Vec::new();
+        encode(&DeBruijn::from(self).inline_refs(), &mut bits)?;
+        Ok(bits
+            .into_iter()
+            .map(|b| if b { '1' } else { '0' })
+            .collect())
+    }
+
+    pub fn to_blc_bytes(&self) -> Result<Vec<u8>, BlcError> {
+        let mut bits = Vec::new();
+        encode(&DeBruijn::from(self).inline_refs(), &mut bits)?;
+        Ok(bits
+            .chunks(8)
+            .map(|byte| {
+                let b = byte.iter().fold(0, |ac, bit| ac << 1 | *bit as u8);
+                b << (8 - byte.len())
+            })
+            .collect())
+    }
+
+    /// Reads a string of bits, that may be separated by whitespace
+    pub fn from_blc_bits(src: &str) -> Result<Self, BlcError> {
+        let bits = src
+            .char_indices()
+            .filter(|(_, c)| !c.is_whitespace())
+            .map(|(pos, c)| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(BlcError::BadChar(pos)),
+            });
+        let mut decoder = Decoder { bits, read: 0 };
+        let t = decoder.term(0, 0)?;
+        match decoder.bits.next() {
+            None => Ok(Term::from(&t)),
+            Some(Err(e)) => Err(e),
+            Some(Ok(_)) => Err(BlcError::TrailingBits),
+        }
+    }
+
+    pub fn from_blc_bytes(bytes: &[u8]) -> Result<Self, BlcError> {
+        let bits = bytes
+            .iter()
+            .flat_map(|b| (0..8).rev().map(move |i| Ok(b >> i & 1 == 1)));
+        let mut decoder = Decoder { bits, read: 0 };
+        let t = decoder.term(0, 0)?;
+        // just the padding of the last byte is left
+        if decoder.read.div_ceil(8) != bytes.len() {
+            return Err(BlcError::TrailingBits);
+        }
+        Ok(Term::from(&t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlcError, MAX_DEPTH};
+    use crate::{
+        assert_alpha_eq,
+        encoding::Encoding,
+        fixtures::{abs, app, var},
+        Term,
+    };
+
+    #[test]
+    fn tromp_encodings() {
+        let id = abs(0, var(0));
+        assert_eq!(id.to_blc_bits(), Ok("0010".to_owned()));
+        assert_eq!(id.to_blc_bytes(), Ok(vec![0b0010_0000]));
+        // λx y z . x z (y z)
+        let s = abs(
+            0,
+            abs(1, abs(2, app(app(var(0), var(2)), app(var(1), var(2))))),
+        );
+        let bits = "00000001011110100111010";
+        assert_eq!(s.to_blc_bits().as_deref(), Ok(bits));
+        assert_alpha_eq!(Term::from_blc_bits(bits).unwrap(), s);
+        assert_alpha_eq!(
+            Term::from_blc_bits("00 00 110").unwrap(),
+            abs(0, abs(1, var(0)))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for t in [
+            Term::church_nat(7),
+            Encoding::Scott.list([Term::church_bool(true), Term::church_nat(2)]),
+            Term::church_pair(Term::church_nil(), Encoding::Parigot.nat(3)),
+        ] {
+            let bits = t.to_blc_bits().unwrap();
+            assert_alpha_eq!(Term::from_blc_bits(&bits).unwrap(), t);
+            let bytes = t.to_blc_bytes().unwrap();
+            assert_eq!(bytes.len(), bits.len().div_ceil(8));
+            assert_alpha_eq!(Term::from_blc_bytes(&bytes).unwrap(), t);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            abs(0, app(var(0), var(1))).to_blc_bits(),
+            Err(BlcError::FreeVar(1))
+        );
+        assert_eq!(Term::from_blc_bits("00110"), Err(BlcError::UnboundIndex(2)));
+        assert_eq!(Term::from_blc_bits("0001"), Err(BlcError::UnexpectedEnd));
+        assert_eq!(Term::from_blc_bits("00102"), Err(BlcError::BadChar(4)));
+        assert_eq!(Term::from_blc_bits("00100"), Err(BlcError::TrailingBits));
+        assert_eq!(
+            Term::from_blc_bytes(&[0b0010_0000, 0]),
+            Err(BlcError::TrailingBits)
+        );
+
+        // λλ…1, with an abstraction less than the nodes
+        let deep = |depth: usize| "00".repeat(depth - 1) + "10";
+        assert_eq!(
+            Term::from_blc_bits(&deep(MAX_DEPTH)).map(|t| t.depth()),
+            Ok(MAX_DEPTH)
+        );
+        assert_eq!(
+            Term::from_blc_bits(&deep(MAX_DEPTH + 1)),
+            Err(BlcError::TooDeep)
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use std::{collections::HashSet, fmt};
+
+use crate::term::write_alias;
+use crate::{Body, Ref, Term};
+
+/// A nameless lambda term, using the locally nameless representation:
+/// bound variables are de Bruijn indices (0 is the innermost binder) and free
+/// variables keep their `Term` names, so alpha-equivalent terms are equal.
+#[derive(Eq, Clone, PartialEq, Debug, Hash)]
+pub enum DeBruijn {
+    Bound(usize),
+    Free(usize),
+    App(Box<DeBruijn>, Box<DeBruijn>),
+    Abs(Box<DeBruijn>),
+    Ref(Ref),
+}
+
+impl DeBruijn {
+    /// Adds `d` to every bound index that escapes `cutoff` binders
+    pub fn shift(&mut self, d: isize, cutoff: usize) {
+        match self {
+            Self::Bound(i) => {
+                if *i >= cutoff {
+                    *i = i
+                        .checked_add_signed(d)
+                        .expect("shifting a bound index below 0");
+                }
+            }
+            Self::Free(..) | Self::Ref(..) => (),
+            Self::App(m, n) => {
+                m.shift(d, cutoff);
+                n.shift(d, cutoff);
+            }
+            Self::Abs(m) => m.shift(d, cutoff + 1),
+        }
+    }
+
+    /// Replaces the bound index `idx` by `val`, no renaming is ever needed
+    pub fn subst(&mut self, idx: usize, val: &Self) {
+        self.subst_under(idx, val, 0)
+    }
+
+    fn subst_under(&mut self, idx: usize, val: &Self, depth: usize) {
+        match self {
+            Self::Bound(i) => {
+                if *i == idx + depth {
+                    let mut val = val.clone();
+                    val.shift(depth as isize, 0);
+                    *self = val;
+                }
+            }
+            Self::Free(..) | Self::Ref(..) => (),
+            Self::App(m, n) => {
+                m.subst_under(idx, val, depth);
+                n.subst_under(idx, val, depth);
+            }
+            Self::Abs(m) => m.subst_under(idx, val, depth + 1),
+        }
+    }
+
+    /// Contracts `(λ body) val`, being `self` the abstraction's body
+    pub fn beta(&mut self, val: &Self) {
+        let mut val = val.clone();
+        val.shift(1, 0);
+        self.subst(0, &val);
+        self.shift(-1, 0);
+    }
+
+    pub fn free_vars(&self) -> HashSet<usize> {
+        let mut frees = HashSet::new();
+        self.free_vars_from(&mut frees);
+        frees
+    }
+
+    fn free_vars_from(&self, frees: &mut HashSet<usize>) {
+        match self {
+            Self::Bound(..) | Self::Ref(..) => (),
+            Self::Free(v) => {
+                frees.insert(*v);
+            }
+            Self::App(m, n) => {
+                m.free_vars_from(frees);
+                n.free_vars_from(frees);
+            }
+            Self::Abs(m) => m.free_vars_from(frees),
+        }
+    }
+
+    fn from_term(t: &Term, binders: &mut Vec<usize>) -> Self {
+        match t.body.as_ref() {
+            Body::Var(v) => binders
+                .iter()
+                .rev()
+                .position(|b| b == v)
+                .map_or(Self::Free(*v), Self::Bound),
+            Body::App(m, n) => Self::App(
+                Self::from_term(m, binders).into(),
+                Self::from_term(n, binders).into(),
+            ),
+            Body::Abs(v, m) => {
+                binders.push(*v);
+                let m = Self::from_term(m, binders);
+                binders.pop();
+                Self::Abs(m.into())
+            }
+            Body::Ref(r) => Self::Ref(r.clone()),
+        }
+    }
+
+    fn to_term(&self, next: &mut usize, binders: &mut Vec<usize>, frees: &HashSet<usize>) -> Term {
+        match self {
+            Self::Bound(i) => Body::Var(binders[binders.len() - 1 - i]).into(),
+            Self::Free(v) => Body::Var(*v).into(),
+            Self::Ref(r) => Body::Ref(r.clone()).into(),
+            Self::App(m, n) => Body::App(
+                m.to_term(next, binders, frees),
+                n.to_term(next, binders, frees),
+            )
+            .into(),
+            Self::Abs(m) => {
+                // Same naming of `Term::unique_alpha_redex`, so the conversion is reversible
+                let v = (*next..).find(|n| !frees.contains(n)).unwrap();
+                *next = v + 1;
+                binders.push(v);
+                let m = m.to_term(next, binders, frees);
+                binders.pop();
+                Body::Abs(v, m).into()
+            }
+        }
+    }
+
+    fn fmt_with(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        paren_app: bool,
+        paren_abs: bool,
+    ) -> fmt::Result {
+        match self {
+            // Printed 1-based, as in de Bruijn's original notation
+            Self::Bound(i) => write!(f, "{}", i + 1),
+            Self::Free(v) => {
+                f.write_str("'")?;
+                write_alias(*v, f)
+            }
+            Self::Ref(r) => f.write_str(r.name()),
+            Self::App(m, n) => {
+                if paren_app {
+                    f.write_str("(")?;
+                }
+                m.fmt_with(f, false, true)?;
+                f.write_str(" ")?;
+                n.fmt_with(f, true, paren_abs && !paren_app)?;
+                if paren_app {
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+            Self::Abs(m) => {
+                if paren_abs {
+                    f.write_str("(")?;
+                }
+                f.write_str("λ ")?;
+                m.fmt_with(f, false, false)?;
+                if paren_abs {
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<&Term> for DeBruijn {
+    fn from(value: &Term) -> Self {
+        Self::from_term(value, &mut Vec::new())
+    }
+}
+
+impl From<&DeBruijn> for Term {
+    /// Names the binders like `Term::unique_alpha_redex` does
+    fn from(value: &DeBruijn) -> Self {
+        value.to_term(&mut 0, &mut Vec::new(), &value.free_vars())
+    }
+}
+
+/// Writes the term as `λ λ 2 1`, free variables are prefixed by a `'`
+impl fmt::Display for DeBruijn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, false, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeBruijn;
+    use crate::{
+        fixtures::{abs, app, omega, var},
+        Term,
+    };
+
+    #[test]
+    fn nameless_printing() {
+        // λx y . x y
+        let t = abs(0, abs(1, app(var(0), var(1))));
+        assert_eq!(DeBruijn::from(&t).to_string(), "λ λ 2 1");
+
+        // (λx . x) (λx . x) z (λx . x)
+        let id = abs(0, var(0));
+        let t = app(app(app(id.clone(), id.clone()), var(7)), id);
+        if cfg!(feature = "ascii-vars") {
+            assert_eq!(DeBruijn::from(&t).to_string(), "(λ 1) (λ 1) 'h λ 1");
+        } else if cfg!(feature = "aliased-vars") {
+            assert_eq!(DeBruijn::from(&t).to_string(), "(λ 1) (λ 1) 'θ λ 1");
+        } else {
+            assert_eq!(DeBruijn::from(&t).to_string(), "(λ 1) (λ 1) '7 λ 1");
+        }
+
+        // (λx . x x) (λx . x x)
+        assert_eq!(DeBruijn::from(&omega()).to_string(), "(λ 1 1) λ 1 1");
+    }
+
+    #[test]
+    fn lossless_conversion() {
+        // λx . (λy . y x) z x
+        let t = abs(5, app(app(abs(3, app(var(3), var(5))), var(0)), var(5)));
+        let nameless = DeBruijn::from(&t);
+        assert_eq!(
+            nameless,
+            DeBruijn::Abs(
+                DeBruijn::App(
+                    DeBruijn::App(
+                        DeBruijn::Abs(
+                            DeBruijn::App(DeBruijn::Bound(0).into(), DeBruijn::Bound(1).into())
+                                .into()
+                        )
+                        .into(),
+                        DeBruijn::Free(0).into()
+                    )
+                    .into(),
+                    DeBruijn::Bound(0).into()
+                )
+                .into()
+            )
+        );
+        assert_eq!(Term::from(&nameless), t.coerce(Term::unique_alpha_redex));
+        assert_eq!(DeBruijn::from(&Term::from(&nameless)), nameless);
+    }
+
+    #[test]
+    fn nameless_beta() {
+        // (λx y . x y) y ~> λz . y z
+        let DeBruijn::App(m, n) = DeBruijn::from(&app(abs(0, abs(1, app(var(0), var(1)))), var(1)))
+        else {
+            unreachable!()
+        };
+        let DeBruijn::Abs(mut body) = *m else {
+            unreachable!()
+        };
+        body.beta(&n);
+        assert_eq!(*body, DeBruijn::from(&abs(2, app(var(1), var(2)))));
+    }
+}
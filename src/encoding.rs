@@ -0,0 +1,425 @@
+//! Church encodings of data, matching the definitions of `assets/list.ch`:
+//! numerals are `λf x . fⁿ x`, booleans are `λx y . x` and `λx y . y`,
+//! pairs are `λz . z a b`, and lists are nested pairs ended by
+//! `Nil = λ_ x y . x`.
+//!
+//! Numerals and lists also have Scott encodings, where a value is its own
+//! pattern matching (`succ n = λs z . s n`), and Parigot encodings, that also
+//! carry the recursion over the predecessor (`succ n = λs z . s n (n s z)`).
+//! `Encoding` builds, reads and converts between all of them.
+
+use crate::{Body, Term};
+
+fn var(v: usize) -> Term {
+    Body::Var(v).into()
+}
+
+fn app(m: Term, n: Term) -> Term {
+    Body::App(m, n).into()
+}
+
+fn abs(v: usize, m: Term) -> Term {
+    Body::Abs(v, m).into()
+}
+
+/// A variable that isn't used by any of `ts`
+fn fresh_var<'a>(ts: impl IntoIterator<Item = &'a Term>) -> usize {
+    ts.into_iter()
+        .flat_map(|t| t.free_vars().into_iter().chain(t.bounded_vars()))
+        .max()
+        .map_or(0, |v| v + 1)
+}
+
+impl Term {
+    /// `λf . (λx . f (λv . x x v)) (λx . f (λv . x x v))`, the fixpoint
+    /// combinator that strict evaluations can unfold too
+    pub fn fixpoint() -> Self {
+        let half = abs(1, app(var(0), abs(2, app(app(var(1), var(1)), var(2)))));
+        abs(0, app(half.clone(), half))
+    }
+
+    pub fn church_nat(n: usize) -> Self {
+        abs(0, abs(1, (0..n).fold(var(1), |ac, _| app(var(0), ac))))
+    }
+
+    pub fn as_church_nat(&self) -> Option<usize> {
+        let Body::Abs(f, t) = self.body.as_ref() else {
+            return None;
+        };
+        let Body::Abs(x, t) = t.body.as_ref() else {
+            return None;
+        };
+        let mut t = t;
+        if f == x {
+            return None;
+        }
+        let mut n = 0;
+        loop {
+            match t.body.as_ref() {
+                Body::Var(v) if v == x => return Some(n),
+                Body::App(m, rest) if *m.body == Body::Var(*f) => {
+                    n += 1;
+                    t = rest;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    pub fn church_bool(b: bool) -> Self {
+        abs(0, abs(1, var(if b { 0 } else { 1 })))
+    }
+
+    /// Decodes a boolean, beware that `false` is also the numeral `0`
+    pub fn as_church_bool(&self) -> Option<bool> {
+        [true, false]
+            .into_iter()
+            .find(|b| self.alpha_eq(&Self::church_bool(*b)))
+    }
+
+    pub fn church_pair(a: Self, b: Self) -> Self {
+        let z = fresh_var([&a, &b]);
+        abs(z, app(app(var(z), a), b))
+    }
+
+    pub fn as_church_pair(&self) -> Option<(&Self, &Self)> {
+        let Body::Abs(z, t) = self.body.as_ref() else {
+            return None;
+        };
+        let Body::App(m, b) = t.body.as_ref() else {
+            return None;
+        };
+        let Body::App(head, a) = m.body.as_ref() else {
+            return None;
+        };
+        let captures = |t: &Self| t.free_vars().contains(z);
+        (*head.body == Body::Var(*z) && !captures(a) && !captures(b)).then_some((a, b))
+    }
+
+    pub fn church_nil() -> Self {
+        abs(0, abs(1, abs(2, var(1))))
+    }
+
+    pub fn church_list(items: impl IntoIterator<Item = Self>) -> Self {
+        let items: Vec<_> = items.into_iter().collect();
+        items.into_iter().rfold(Self::church_nil(), |tail, head| {
+            Self::church_pair(head, tail)
+        })
+    }
+
+    pub fn as_church_list(&self) -> Option<Vec<&Self>> {
+        let nil = Self::church_nil();
+        let mut items = Vec::new();
+        let mut t = self;
+        while !t.alpha_eq(&nil) {
+            let (head, tail) = t.as_church_pair()?;
+            items.push(head);
+            t = tail;
+        }
+        Some(items)
+    }
+}
+
+/// A representation of numerals and lists
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Encoding {
+    /// `n = λf x . fⁿ x`, `[h, ..t] = λz . z h t` and `[] = λ_ x y . x`
+    Church,
+    /// `n + 1 = λs z . s n`, `[h, ..t] = λc n . c h t` and `0 = [] = λs z . z`
+    Scott,
+    /// `n + 1 = λs z . s n (n s z)`, `[h, ..t] = λc n . c h t (t c n)`
+    /// and `0 = [] = λs z . z`
+    Parigot,
+}
+
+/// `λs z . s`ᵢ `p` `r`, the shape of Scott and Parigot values, with the
+/// arguments after `s` if they don't depend on `s` and `z`
+fn constructor(t: &Term, args: usize) -> Option<(usize, usize, Vec<&Term>)> {
+    let Body::Abs(s, t) = t.body.as_ref() else {
+        return None;
+    };
+    let Body::Abs(z, t) = t.body.as_ref() else {
+        return None;
+    };
+    if s == z {
+        return None;
+    }
+    let mut args_rev = Vec::with_capacity(args);
+    let mut t = t;
+    for _ in 0..args {
+        let Body::App(m, n) = t.body.as_ref() else {
+            return None;
+        };
+        args_rev.push(n);
+        t = m;
+    }
+    if *t.body != Body::Var(*s) {
+        return None;
+    }
+    args_rev.reverse();
+    Some((*s, *z, args_rev))
+}
+
+/// Checks if `t` is `λs z . z`, for any `s` and `z`
+fn is_zero(t: &Term) -> bool {
+    t.alpha_eq(&abs(0, abs(1, var(1))))
+}
+
+/// Checks if `r` is the recursion over `p` of a Parigot value, `p s z`
+fn is_recursion(s: usize, z: usize, p: &Term, r: &Term) -> bool {
+    abs(s, abs(z, r.clone())).alpha_eq(p)
+}
+
+fn closed_over(s: usize, z: usize, ts: &[&Term]) -> bool {
+    ts.iter().all(|t| {
+        let frees = t.free_vars();
+        !frees.contains(&s) && !frees.contains(&z)
+    })
+}
+
+impl Encoding {
+    pub const ALL: [Self; 3] = [Self::Church, Self::Scott, Self::Parigot];
+
+    pub fn nat(self, n: usize) -> Term {
+        match self {
+            Self::Church => Term::church_nat(n),
+            Self::Scott => (0..n).fold(abs(0, abs(1, var(1))), |p, _| {
+                abs(0, abs(1, app(var(0), p)))
+            }),
+            Self::Parigot => {
+                // the body of the predecessor, `p s z`
+                let mut r = var(1);
+                let mut p = abs(0, abs(1, r.clone()));
+                for _ in 0..n {
+                    r = app(app(var(0), p), r);
+                    p = abs(0, abs(1, r.clone()));
+                }
+                p
+            }
+        }
+    }
+
+    pub fn as_nat(self, t: &Term) -> Option<usize> {
+        if self == Self::Church {
+            return t.as_church_nat();
+        }
+        let mut n = 0;
+        let mut t = t;
+        while !is_zero(t) {
+            let (s, z, args) = constructor(t, if self == Self::Scott { 1 } else { 2 })?;
+            if !closed_over(s, z, &args[..1])
+                || (self == Self::Parigot && !is_recursion(s, z, args[0], args[1]))
+            {
+                return None;
+            }
+            n += 1;
+            t = args[0];
+        }
+        Some(n)
+    }
+
+    pub fn list(self, items: impl IntoIterator<Item = Term>) -> Term {
+        if self == Self::Church {
+            return Term::church_list(items);
+        }
+        let items: Vec<_> = items.into_iter().collect();
+        let c = fresh_var(&items);
+        let n = c + 1;
+        let mut t = abs(c, abs(n, var(n)));
+        // the body of the tail, `t c n`
+        let mut r = var(n);
+        for h in items.into_iter().rev() {
+            let body = app(app(var(c), h), t);
+            r = match self {
+                Self::Scott => body,
+                _ => app(body, r),
+            };
+            t = abs(c, abs(n, r.clone()));
+        }
+        t
+    }
+
+    pub fn as_list(self, t: &Term) -> Option<Vec<&Term>> {
+        if self == Self::Church {
+            return t.as_church_list();
+        }
+        let mut items = Vec::new();
+        let mut t = t;
+        while !is_zero(t) {
+            let (c, n, args) = constructor(t, if self == Self::Scott { 2 } else { 3 })?;
+            if !closed_over(c, n, &args[..2])
+                || (self == Self::Parigot && !is_recursion(c, n, args[1], args[2]))
+            {
+                return None;
+            }
+            items.push(args[0]);
+            t = args[1];
+        }
+        Some(items)
+    }
+
+    /// `λm . m + 1`
+    fn succ(self) -> Term {
+        let body = match self {
+            Self::Church => app(var(1), app(app(var(0), var(1)), var(2))),
+            Self::Scott => app(var(1), var(0)),
+            Self::Parigot => app(app(var(1), var(0)), app(app(var(0), var(1)), var(2))),
+        };
+        abs(0, abs(1, abs(2, body)))
+    }
+
+    /// `λh t . [h, ..t]`
+    fn cons(self) -> Term {
+        let body = match self {
+            Self::Church => return abs(0, abs(1, abs(2, app(app(var(2), var(0)), var(1))))),
+            Self::Scott => app(app(var(2), var(0)), var(1)),
+            Self::Parigot => app(
+                app(app(var(2), var(0)), var(1)),
+                app(app(var(1), var(2)), var(3)),
+            ),
+        };
+        abs(0, abs(1, abs(2, abs(3, body))))
+    }
+
+    /// A term that converts a numeral of this encoding to the `to` one
+    pub fn nat_conversion(self, to: Self) -> Term {
+        let (zero, succ) = (to.nat(0), to.succ());
+        match self {
+            // λn . n succ zero
+            Self::Church => abs(0, app(app(var(0), succ), zero)),
+            // λn . n (λ_ r . succ r) zero
+            Self::Parigot => {
+                let step = abs(1, abs(2, app(succ, var(2))));
+                abs(0, app(app(var(0), step), zero))
+            }
+            // Y (λrec n . n (λp . succ (rec p)) zero)
+            Self::Scott => {
+                let step = abs(2, app(succ, app(var(0), var(2))));
+                app(
+                    Term::fixpoint(),
+                    abs(0, abs(1, app(app(var(1), step), zero))),
+                )
+            }
+        }
+    }
+
+    /// A term that converts a list of this encoding to the `to` one,
+    /// keeping its items as they are
+    pub fn list_conversion(self, to: Self) -> Term {
+        let (nil, cons) = (to.list([]), to.cons());
+        // λh t . cons h (rec t)
+        let recurse = abs(
+            2,
+            abs(3, app(app(cons.clone(), var(2)), app(var(0), var(3)))),
+        );
+        match self {
+            // Y (λrec l . l (λh t _ k . k h t) nil (λh t . cons h (rec t)))
+            Self::Church => {
+                let split = abs(2, abs(3, abs(4, abs(5, app(app(var(5), var(2)), var(3))))));
+                let body = app(app(app(var(1), split), nil), recurse);
+                app(Term::fixpoint(), abs(0, abs(1, body)))
+            }
+            // Y (λrec l . l (λh t . cons h (rec t)) nil)
+            Self::Scott => app(
+                Term::fixpoint(),
+                abs(0, abs(1, app(app(var(1), recurse), nil))),
+            ),
+            // λl . l (λh _ r . cons h r) nil
+            Self::Parigot => {
+                let step = abs(1, abs(2, abs(3, app(app(cons, var(1)), var(3)))));
+                abs(0, app(app(var(0), step), nil))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoding;
+    use crate::{
+        assert_alpha_eq,
+        fixtures::{abs, app, plus, var},
+        strategy::Normal,
+        Fuel, Term,
+    };
+
+    #[test]
+    fn numerals() {
+        for n in 0..5 {
+            assert_eq!(Term::church_nat(n).as_church_nat(), Some(n));
+        }
+        let mut t = app(app(plus(), Term::church_nat(2)), Term::church_nat(3));
+        t.normalize(&Normal, Fuel::UNBOUNDED);
+        assert_eq!(t.as_church_nat(), Some(5));
+
+        // λx x . x isn't a numeral, the inner binder shadows the outer one
+        assert_eq!(abs(0, abs(0, var(0))).as_church_nat(), None);
+        assert_eq!(Term::church_nil().as_church_nat(), None);
+    }
+
+    #[test]
+    fn booleans_and_pairs() {
+        assert_eq!(Term::church_bool(true).as_church_bool(), Some(true));
+        assert_eq!(Term::church_bool(false).as_church_bool(), Some(false));
+        assert_eq!(Term::church_nat(1).as_church_bool(), None);
+
+        // (λx y z . z x y) a b
+        let pair = abs(0, abs(1, abs(2, app(app(var(2), var(0)), var(1)))));
+        let mut t = app(app(pair, var(3)), var(4));
+        t.normalize(&Normal, Fuel::UNBOUNDED);
+        assert_alpha_eq!(t, Term::church_pair(var(3), var(4)));
+        assert_eq!(t.as_church_pair(), Some((&var(3), &var(4))));
+        // λz . z z b isn't a pair
+        assert_eq!(
+            abs(0, app(app(var(0), var(0)), var(1))).as_church_pair(),
+            None
+        );
+    }
+
+    #[test]
+    fn encodings() {
+        for e in Encoding::ALL {
+            for n in 0..4 {
+                assert_eq!(e.as_nat(&e.nat(n)), Some(n), "{e:?}");
+            }
+            let items = [var(0), Term::church_nat(2), var(1)];
+            let list = e.list(items.clone());
+            assert!(e.as_list(&list).unwrap().into_iter().eq(&items), "{e:?}");
+        }
+        let scott = Encoding::Scott.nat(2);
+        assert_eq!(Encoding::Church.as_nat(&scott), None);
+        assert_eq!(Encoding::Parigot.as_nat(&scott), None);
+        let parigot = Encoding::Parigot.list([var(0)]);
+        assert_eq!(Encoding::Scott.as_list(&parigot), None);
+    }
+
+    #[test]
+    fn conversions() {
+        for from in Encoding::ALL {
+            for to in Encoding::ALL {
+                let mut t = app(from.nat_conversion(to), from.nat(3));
+                t.normalize(&Normal, Fuel::steps(1_000));
+                assert_eq!(to.as_nat(&t), Some(3), "{from:?} to {to:?}");
+
+                let items = [var(0), var(1)];
+                let mut t = app(from.list_conversion(to), from.list(items.clone()));
+                t.normalize(&Normal, Fuel::steps(1_000));
+                let converted = to.as_list(&t);
+                assert!(
+                    converted.unwrap().into_iter().eq(&items),
+                    "{from:?} to {to:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lists() {
+        let nats = || (1..4).map(Term::church_nat);
+        let list = Term::church_list(nats());
+        let items = list.as_church_list().unwrap();
+        assert!(items.into_iter().cloned().eq(nats()));
+        assert_eq!(Term::church_nil().as_church_list(), Some(Vec::new()));
+        assert_eq!(Term::church_nat(2).as_church_list(), None);
+    }
+}
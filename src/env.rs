@@ -0,0 +1,101 @@
+//! What the evaluators without substitution share: environments of closures,
+//! the heads of stuck terms, and the reading back of their normal forms, which
+//! is where the fuel's size is enforced, as they build no intermediate terms.
+
+use std::{cell::Cell, rc::Rc};
+
+use crate::{DeBruijn, Fuel, Normalization, Outcome, Ref, Term};
+
+/// Linked list of entries, indexed by de Bruijn indices
+pub(crate) type Env<E> = Option<Rc<Frame<E>>>;
+
+pub(crate) struct Frame<E> {
+    entry: E,
+    next: Env<E>,
+}
+
+pub(crate) fn lookup<E: Clone>(mut env: &Env<E>, idx: usize) -> E {
+    for _ in 0..idx {
+        env = &env.as_ref().expect("unbound de Bruijn index").next;
+    }
+    env.as_ref().expect("unbound de Bruijn index").entry.clone()
+}
+
+pub(crate) fn extend<E>(env: Env<E>, entry: E) -> Env<E> {
+    Some(Rc::new(Frame { entry, next: env }))
+}
+
+/// What a stuck term is applied to
+#[derive(Clone, Copy)]
+pub(crate) enum Head<'a> {
+    Free(usize),
+    /// A variable introduced while reading back an abstraction, counted from the root
+    Level(usize),
+    /// A reference that isn't applied, so it isn't unfolded, unless it's read
+    /// back and its definition isn't normal
+    Ref(&'a Ref),
+}
+
+/// Counts the steps taken and the nodes read back, failing when the fuel is exhausted
+pub(crate) struct Counter {
+    steps: Cell<usize>,
+    nodes: Cell<usize>,
+    fuel: Fuel,
+}
+
+impl Counter {
+    pub(crate) fn new(fuel: Fuel) -> Self {
+        Self {
+            steps: Cell::new(0),
+            nodes: Cell::new(0),
+            fuel,
+        }
+    }
+
+    /// Counts a β-reduction or the unfolding of a reference
+    pub(crate) fn tick(&self) -> Result<(), Outcome> {
+        self.fuel.check(self.steps.get())?;
+        self.steps.set(self.steps.get() + 1);
+        Ok(())
+    }
+
+    fn node(&self, t: DeBruijn) -> Result<DeBruijn, Outcome> {
+        self.nodes.set(self.nodes.get() + 1);
+        if self.nodes.get() > self.fuel.size {
+            return Err(Outcome::SizeExceeded);
+        }
+        Ok(t)
+    }
+
+    /// Reads back a head under `depth` binders
+    pub(crate) fn head(&self, h: Head, depth: usize) -> Result<DeBruijn, Outcome> {
+        self.node(match h {
+            Head::Free(v) => DeBruijn::Free(v),
+            Head::Level(l) => DeBruijn::Bound(depth - 1 - l),
+            Head::Ref(r) => DeBruijn::Ref(r.clone()),
+        })
+    }
+
+    pub(crate) fn app(&self, m: DeBruijn, n: DeBruijn) -> Result<DeBruijn, Outcome> {
+        self.node(DeBruijn::App(m.into(), n.into()))
+    }
+
+    pub(crate) fn abs(&self, body: DeBruijn) -> Result<DeBruijn, Outcome> {
+        self.node(DeBruijn::Abs(body.into()))
+    }
+
+    /// Replaces the term by its normal form, if it was reached
+    pub(crate) fn finish(&self, t: &mut Term, res: Result<DeBruijn, Outcome>) -> Normalization {
+        let outcome = match res {
+            Ok(nf) => {
+                *t = Term::from(&nf);
+                Outcome::Normal
+            }
+            Err(outcome) => outcome,
+        };
+        Normalization {
+            outcome,
+            steps: self.steps.get(),
+        }
+    }
+}
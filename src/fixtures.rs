@@ -0,0 +1,28 @@
+//! Shorthands for building the terms of the tests
+
+use crate::{Body, Term};
+
+pub(crate) fn var(v: usize) -> Term {
+    Body::Var(v).into()
+}
+
+pub(crate) fn app(m: Term, n: Term) -> Term {
+    Body::App(m, n).into()
+}
+
+pub(crate) fn abs(v: usize, m: Term) -> Term {
+    Body::Abs(v, m).into()
+}
+
+/// `(λx . x x) (λx . x x)`
+pub(crate) fn omega() -> Term {
+    let delta = abs(0, app(var(0), var(0)));
+    app(delta.clone(), delta)
+}
+
+/// `λm n f x . m f (n f x)`, the sum of Church numerals
+pub(crate) fn plus() -> Term {
+    let (m, n, f, x) = (var(0), var(1), var(2), var(3));
+    let body = app(app(m, f.clone()), app(app(n, f), x));
+    abs(0, abs(1, abs(2, abs(3, body))))
+}
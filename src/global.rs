@@ -0,0 +1,325 @@
+//! Named global definitions, referred by terms instead of being copied into
+//! them. A reference stays folded, printed by its name, until it's applied,
+//! when it unfolds to its definition (a δ-reduction). The strong strategies,
+//! and the machines while reading back, also unfold the references whose
+//! definitions aren't normal, so the normal form is the same as when inlined.
+
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, OnceLock},
+};
+
+use crate::{Body, DeBruijn, Term};
+
+struct Global {
+    name: String,
+    term: OnceLock<Term>,
+    /// The term for the abstract machines, converted on its first unfolding
+    nameless: OnceLock<DeBruijn>,
+    /// If the definition is normal, checked on its first unfolding
+    normal: OnceLock<bool>,
+}
+
+/// A shared reference to a global definition. Two references are equal when
+/// they refer to the same definition, not just to one with the same name.
+///
+/// A recursive definition holds references to itself, directly or through the
+/// definitions it refers to, so it's never freed: the globals are meant to
+/// live as long as the program that defines them, like the REPL's scope.
+#[derive(Clone)]
+pub struct Ref(Arc<Global>);
+
+impl Ref {
+    /// A reference to a definition that's given later by `Ref::define`, so it
+    /// can refer to itself, which leaks it (see `Ref`)
+    pub fn declare(name: impl Into<String>) -> Self {
+        Self(Arc::new(Global {
+            name: name.into(),
+            term: OnceLock::new(),
+            nameless: OnceLock::new(),
+            normal: OnceLock::new(),
+        }))
+    }
+
+    pub fn new(name: impl Into<String>, term: Term) -> Self {
+        let r = Self::declare(name);
+        // a new reference can't be defined yet
+        let _ = r.define(term);
+        r
+    }
+
+    /// Gives the definition of a declared reference, returning the term back
+    /// when it's already defined
+    pub fn define(&self, term: Term) -> Result<(), Term> {
+        self.0.term.set(term)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// The definition, panics when the reference was just declared
+    pub fn term(&self) -> &Term {
+        self.0
+            .term
+            .get()
+            .unwrap_or_else(|| panic!("`{}` is declared but not defined", self.0.name))
+    }
+
+    pub(crate) fn nameless(&self) -> &DeBruijn {
+        self.0.nameless.get_or_init(|| DeBruijn::from(self.term()))
+    }
+
+    /// If the definition is on its normal form, where the references it keeps
+    /// folded have to be normal too, so a reference is unfolded just when
+    /// it's going to be reduced
+    pub fn is_normal(&self) -> bool {
+        *self
+            .0
+            .normal
+            .get_or_init(|| self.term().is_normal_with(&mut vec![self.clone()]))
+    }
+}
+
+/// A reference being inlined, at the binder of its fixpoint
+struct Inlining {
+    r: Ref,
+    level: usize,
+    recursive: bool,
+}
+
+impl DeBruijn {
+    /// Replaces the references by their definitions, the ones that refer to
+    /// themselves, directly or not, through a fixpoint
+    pub fn inline_refs(&self) -> Self {
+        self.inline_with(&mut Vec::new(), 0)
+    }
+
+    fn inline_with(&self, inlining: &mut Vec<Inlining>, depth: usize) -> Self {
+        match self {
+            Self::Bound(..) | Self::Free(..) => self.clone(),
+            Self::App(m, n) => Self::App(
+                m.inline_with(inlining, depth).into(),
+                n.inline_with(inlining, depth).into(),
+            ),
+            Self::Abs(m) => Self::Abs(m.inline_with(inlining, depth + 1).into()),
+            Self::Ref(r) => {
+                if let Some(outer) = inlining.iter_mut().find(|i| i.r == *r) {
+                    outer.recursive = true;
+                    return Self::Bound(depth - 1 - outer.level);
+                }
+                // the definition is inlined under the binder of the fixpoint,
+                // which is dropped when it's not used
+                inlining.push(Inlining {
+                    r: r.clone(),
+                    level: depth,
+                    recursive: false,
+                });
+                let mut def = r.nameless().inline_with(inlining, depth + 1);
+                if inlining.pop().unwrap().recursive {
+                    Self::App(
+                        Self::from(&Term::fixpoint()).into(),
+                        Self::Abs(def.into()).into(),
+                    )
+                } else {
+                    def.shift(-1, 0);
+                    def
+                }
+            }
+        }
+    }
+}
+
+impl Term {
+    pub(crate) fn has_refs(&self) -> bool {
+        match self.body.as_ref() {
+            Body::Var(..) => false,
+            Body::App(m, n) => m.has_refs() || n.has_refs(),
+            Body::Abs(_, m) => m.has_refs(),
+            Body::Ref(..) => true,
+        }
+    }
+
+    /// The reference that's unfolded by contracting the term, when it's an
+    /// applied reference or a δ-redex
+    pub fn unfolds(&self) -> Option<&Ref> {
+        match self.body.as_ref() {
+            Body::App(m, _) => match m.body.as_ref() {
+                Body::Ref(r) => Some(r),
+                _ => None,
+            },
+            Body::Ref(r) if !r.is_normal() => Some(r),
+            Body::Var(..) | Body::Abs(..) | Body::Ref(..) => None,
+        }
+    }
+
+    /// If it has no redexes, being normal the references that are being
+    /// checked, as a definition that just refers to itself can't be reduced
+    fn is_normal_with(&self, checking: &mut Vec<Ref>) -> bool {
+        match self.body.as_ref() {
+            _ if self.is_redex() => false,
+            Body::Var(..) => true,
+            Body::App(m, n) => m.is_normal_with(checking) && n.is_normal_with(checking),
+            Body::Abs(_, m) => m.is_normal_with(checking),
+            Body::Ref(r) => {
+                if let Some(normal) = r.0.normal.get() {
+                    return *normal;
+                }
+                if checking.contains(r) {
+                    return true;
+                }
+                checking.push(r.clone());
+                let normal = r.term().is_normal_with(checking);
+                checking.pop();
+                normal
+            }
+        }
+    }
+
+    /// Same as `DeBruijn::inline_refs`, keeping the names of the variables
+    /// outside the references, as the definitions are closed
+    pub fn inline_refs(&self) -> Self {
+        match self.body.as_ref() {
+            Body::Var(..) => self.clone(),
+            Body::App(m, n) => Body::App(m.inline_refs(), n.inline_refs()).into(),
+            Body::Abs(v, m) => Body::Abs(*v, m.inline_refs()).into(),
+            Body::Ref(r) => Self::from(&DeBruijn::Ref(r.clone()).inline_refs()),
+        }
+    }
+}
+
+impl PartialEq for Ref {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Ref {}
+
+impl Hash for Ref {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.name.hash(state)
+    }
+}
+
+/// Just the name, as recursive definitions refer to themselves
+impl fmt::Debug for Ref {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ref({})", self.0.name)
+    }
+}
+
+impl fmt::Display for Ref {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ref;
+    use crate::{
+        assert_alpha_eq,
+        fixtures::{abs, app, var},
+        strategy::{Applicative, Head, LeftmostInnermost, Normal, ReductionStrategy},
+        Body, Fuel, Outcome, Term,
+    };
+
+    #[test]
+    fn unfolds_when_applied() {
+        let id = Ref::new("Id", abs(0, var(0)));
+        let k = Ref::new("K", abs(0, abs(1, var(0))));
+        // K Id ~> (λx y . x) Id ~> λy . Id
+        let t = app(Body::Ref(k).into(), Body::Ref(id.clone()).into());
+        let folded = abs(1, Body::Ref(id).into());
+
+        let mut normal = t.clone();
+        let res = normal.normalize(&Normal, Fuel::UNBOUNDED);
+        assert_eq!((res.outcome, res.steps), (Outcome::Normal, 2));
+        assert_alpha_eq!(normal, folded);
+        // printed inlined, so it's read back
+        assert_alpha_eq!(
+            normal.to_string().parse::<Term>().unwrap(),
+            abs(1, abs(0, var(0)))
+        );
+
+        let machines = [
+            Term::normalize_krivine,
+            Term::normalize_cek,
+            Term::normalize_by_need,
+            Term::normalize_nbe,
+        ];
+        for normalize in machines {
+            let mut m = t.clone();
+            assert_eq!(normalize(&mut m, Fuel::UNBOUNDED).outcome, Outcome::Normal);
+            assert_alpha_eq!(m, folded);
+        }
+    }
+
+    #[test]
+    fn inlines_recursion() {
+        // F = λx . x F
+        let f = Ref::declare("F");
+        f.define(abs(0, app(var(0), Body::Ref(f.clone()).into())))
+            .unwrap();
+        let t = app(Body::Ref(f).into(), abs(1, abs(2, var(2))));
+        let inlined = t.inline_refs();
+        assert!(!inlined.has_refs());
+        assert!(inlined.to_blc_bits().is_ok());
+
+        for mut t in [t, inlined] {
+            let res = t.normalize(&Normal, Fuel::steps(100));
+            assert_eq!(res.outcome, Outcome::Normal);
+            assert_alpha_eq!(t, abs(2, var(2)));
+        }
+    }
+
+    #[test]
+    fn unfolds_when_not_normal() {
+        // One = λf x . f x, Succ = λn f x . f (n f x), Two = Succ One
+        let one = Ref::new("One", abs(0, abs(1, app(var(0), var(1)))));
+        let succ = Ref::new(
+            "Succ",
+            abs(
+                2,
+                abs(0, abs(1, app(var(0), app(app(var(2), var(0)), var(1))))),
+            ),
+        );
+        let succ_one = app(
+            Body::Ref(succ.clone()).into(),
+            Body::Ref(one.clone()).into(),
+        );
+        let two = Ref::new("Two", succ_one.clone());
+        assert!(one.is_normal() && !two.is_normal());
+        assert_eq!(succ_one.unfolds(), Some(&succ));
+        assert_eq!(Term::from(Body::Ref(one.clone())).unfolds(), None);
+        let nf = abs(0, abs(1, app(var(0), app(var(0), var(1)))));
+
+        let strategies: [&dyn ReductionStrategy; 4] =
+            [&Normal, &Applicative, &Head, &LeftmostInnermost];
+        for s in strategies {
+            let [two, succ_one] = [Body::Ref(two.clone()).into(), succ_one.clone()].map(|mut t| {
+                assert_eq!(t.normalize(s, Fuel::UNBOUNDED).outcome, Outcome::Normal);
+                t
+            });
+            assert_alpha_eq!(two, succ_one, "{} stopped at different terms", s.name());
+            // the head normal form of `Succ One` is λf x . f (One f x)
+            if s.name() != "head" {
+                assert_alpha_eq!(two, nf);
+            }
+        }
+
+        let machines = [
+            Term::normalize_krivine,
+            Term::normalize_cek,
+            Term::normalize_by_need,
+            Term::normalize_nbe,
+        ];
+        for normalize in machines {
+            let mut t: Term = Body::Ref(two.clone()).into();
+            assert_eq!(normalize(&mut t, Fuel::UNBOUNDED).outcome, Outcome::Normal);
+            assert_alpha_eq!(t, nf);
+        }
+    }
+}
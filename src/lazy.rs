@@ -0,0 +1,195 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    env::{extend, lookup, Counter, Env, Head},
+    DeBruijn, Fuel, Normalization, Outcome, Term,
+};
+
+/// An argument that's evaluated at most once, when it's first needed
+type Thunk<'a> = Rc<RefCell<ThunkState<'a>>>;
+
+enum ThunkState<'a> {
+    Delayed(&'a DeBruijn, Env<Thunk<'a>>),
+    Forced(Value<'a>),
+    /// Being forced right now
+    Blackhole,
+}
+
+/// A weak head normal form
+#[derive(Clone)]
+enum Value<'a> {
+    Closure(&'a DeBruijn, Env<Thunk<'a>>),
+    Neutral(Head<'a>, Vec<Thunk<'a>>),
+}
+
+/// What to do with the value of the current term
+enum Cont<'a> {
+    /// Apply it to this argument
+    Arg(Thunk<'a>),
+    /// Memoize it on this thunk
+    Update(Thunk<'a>),
+}
+
+struct Machine(Counter);
+
+impl<'a> Machine {
+    fn delay(t: &'a DeBruijn, env: Env<Thunk<'a>>) -> Thunk<'a> {
+        Rc::new(RefCell::new(ThunkState::Delayed(t, env)))
+    }
+
+    /// Evaluates a term to its weak head normal form, iteratively, so long
+    /// reductions don't overflow the stack
+    fn eval(&self, t: &'a DeBruijn, env: Env<Thunk<'a>>) -> Result<Value<'a>, Outcome> {
+        self.run(t, env, Vec::new())
+    }
+
+    fn run(
+        &self,
+        mut t: &'a DeBruijn,
+        mut env: Env<Thunk<'a>>,
+        mut stack: Vec<Cont<'a>>,
+    ) -> Result<Value<'a>, Outcome> {
+        'eval: loop {
+            let mut v = match t {
+                DeBruijn::App(m, n) => {
+                    stack.push(Cont::Arg(Self::delay(n, env.clone())));
+                    t = m;
+                    continue;
+                }
+                DeBruijn::Bound(i) => {
+                    let thunk = lookup(&env, *i);
+                    let state = std::mem::replace(&mut *thunk.borrow_mut(), ThunkState::Blackhole);
+                    match state {
+                        ThunkState::Forced(v) => {
+                            *thunk.borrow_mut() = ThunkState::Forced(v.clone());
+                            v
+                        }
+                        ThunkState::Delayed(nt, nenv) => {
+                            stack.push(Cont::Update(thunk));
+                            (t, env) = (nt, nenv);
+                            continue;
+                        }
+                        // Without `letrec`, a thunk can't reach itself from its own environment
+                        ThunkState::Blackhole => unreachable!("thunk forced while being forced"),
+                    }
+                }
+                DeBruijn::Free(v) => Value::Neutral(Head::Free(*v), Vec::new()),
+                DeBruijn::Abs(b) => Value::Closure(b, env.clone()),
+                DeBruijn::Ref(r) => Value::Neutral(Head::Ref(r), Vec::new()),
+            };
+            while let Some(cont) = stack.pop() {
+                match (cont, v) {
+                    (Cont::Update(thunk), nv) => {
+                        *thunk.borrow_mut() = ThunkState::Forced(nv.clone());
+                        v = nv;
+                    }
+                    (Cont::Arg(arg), Value::Closure(b, cenv)) => {
+                        self.0.tick()?;
+                        (t, env) = (b, extend(cenv, arg));
+                        continue 'eval;
+                    }
+                    (Cont::Arg(arg), Value::Neutral(Head::Ref(r), _)) => {
+                        self.0.tick()?;
+                        stack.push(Cont::Arg(arg));
+                        (t, env) = (r.nameless(), None);
+                        continue 'eval;
+                    }
+                    (Cont::Arg(arg), Value::Neutral(h, mut args)) => {
+                        args.push(arg);
+                        v = Value::Neutral(h, args);
+                    }
+                }
+            }
+            return Ok(v);
+        }
+    }
+
+    fn force(&self, thunk: &Thunk<'a>) -> Result<Value<'a>, Outcome> {
+        let state = std::mem::replace(&mut *thunk.borrow_mut(), ThunkState::Blackhole);
+        match state {
+            ThunkState::Forced(v) => {
+                *thunk.borrow_mut() = ThunkState::Forced(v.clone());
+                Ok(v)
+            }
+            ThunkState::Delayed(t, env) => self.run(t, env, vec![Cont::Update(thunk.clone())]),
+            ThunkState::Blackhole => unreachable!("thunk forced while being forced"),
+        }
+    }
+
+    /// Reads back a value to a normal form, evaluating under abstractions
+    fn quote(&self, v: Value<'a>, depth: usize) -> Result<DeBruijn, Outcome> {
+        match v {
+            Value::Closure(b, env) => {
+                let var = ThunkState::Forced(Value::Neutral(Head::Level(depth), Vec::new()));
+                let body = self.eval(b, extend(env, Rc::new(RefCell::new(var))))?;
+                let body = self.quote(body, depth + 1)?;
+                self.0.abs(body)
+            }
+            Value::Neutral(Head::Ref(r), _) if !r.is_normal() => {
+                self.0.tick()?;
+                let v = self.eval(r.nameless(), None)?;
+                self.quote(v, depth)
+            }
+            Value::Neutral(h, args) => {
+                let mut t = self.0.head(h, depth)?;
+                for arg in args {
+                    let arg = self.force(&arg)?;
+                    t = self.0.app(t, self.quote(arg, depth)?)?;
+                }
+                Ok(t)
+            }
+        }
+    }
+}
+
+impl Term {
+    /// Normalizes the term with call-by-need, where each argument is evaluated
+    /// at most once and shared between all its occurrences.
+    pub fn normalize_by_need(&mut self, fuel: Fuel) -> Normalization {
+        let t = DeBruijn::from(&*self);
+        let machine = Machine(Counter::new(fuel));
+        let res = machine.eval(&t, None).and_then(|v| machine.quote(v, 0));
+        machine.0.finish(self, res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assert_alpha_eq,
+        fixtures::{abs, app, omega, var},
+        strategy::Normal,
+        Fuel, Outcome,
+    };
+
+    #[test]
+    fn shares_arguments() {
+        // (λx . x x x) ((λy . y) z)
+        let t = app(
+            abs(0, app(app(var(0), var(0)), var(0))),
+            app(abs(1, var(1)), var(2)),
+        );
+
+        let mut by_need = t.clone();
+        let res = by_need.normalize_by_need(Fuel::UNBOUNDED);
+        assert_eq!(res.outcome, Outcome::Normal);
+        assert_eq!(res.steps, 2);
+
+        let mut by_name = t;
+        let res = by_name.normalize(&Normal, Fuel::UNBOUNDED);
+        assert_eq!(res.steps, 4);
+        assert_alpha_eq!(by_need, by_name);
+    }
+
+    #[test]
+    fn strong_readback() {
+        // λf . (λx y . x) f ((λx . x x) (λx . x x))
+        let mut t = abs(3, app(app(abs(0, abs(1, var(0))), var(3)), omega()));
+        let res = t.normalize_by_need(Fuel::steps(100));
+        assert_eq!(res.outcome, Outcome::Normal);
+        assert_alpha_eq!(t, abs(0, var(0)));
+
+        let res = omega().normalize_by_need(Fuel::steps(100));
+        assert_eq!(res.outcome, Outcome::OutOfFuel);
+    }
+}
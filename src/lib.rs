@@ -1,26 +1,47 @@
+pub mod alias;
+pub mod binary;
+pub mod blc;
+pub mod debruijn;
+pub mod encoding;
+mod env;
+#[cfg(test)]
+mod fixtures;
+pub mod global;
+pub mod lazy;
+pub mod loops;
+pub mod machine;
+pub mod nbe;
+pub mod parse;
+pub mod path;
+pub mod ski;
+pub mod strategy;
 pub mod term;
+pub mod trace;
 
-pub use term::{Body, Term};
+pub use debruijn::DeBruijn;
+pub use global::Ref;
+pub use path::Path;
+pub use strategy::ReductionStrategy;
+pub use term::{AlphaTerm, Body, Fuel, Normalization, Outcome, Term};
+pub use trace::{Trace, TraceStep};
 
 #[macro_export]
 macro_rules! assert_alpha_eq {
     ($left:expr, $right:expr $(,)?) => {
         $crate::assert_alpha_eq!($left, $right, "{} is alpha-different from {}", $left, $right)
      };
-    ($left:expr, $right:expr, $($arg:tt)+) => {{
-        assert_eq!($left.coerce(Term::unique_alpha_redex), $right.coerce(Term::unique_alpha_redex), $($arg)+);
-     }};
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        assert!($crate::Term::alpha_eq(&$left, &$right), $($arg)+)
+     };
 }
 
 #[macro_export]
 macro_rules! assert_alpha_ne {
     ($left:expr, $right:expr $(,)?) => {
-        assert_ne!($left.coerce(Term::unique_alpha_redex),
-                   $right.coerce(Term::unique_alpha_redex))
+        $crate::assert_alpha_ne!($left, $right, "{} is alpha-equivalent to {}", $left, $right)
      };
     ($left:expr, $right:expr, $($arg:tt)+) => {
-        assert_ne!($left.coerce(Term::unique_alpha_redex),
-                   $right.coerce(Term::unique_alpha_redex), $($arg)+)
+        assert!(!$crate::Term::alpha_eq(&$left, &$right), $($arg)+)
      };
 }
 
@@ -31,7 +52,9 @@ mod tests {
     #[test]
     fn id_formatting() {
         let id: Term = Body::Abs(0, Body::Var(0).into()).into();
-        if cfg!(feature = "aliased-vars") {
+        if cfg!(feature = "ascii-vars") {
+            assert_eq!(id.to_string(), "λa.a");
+        } else if cfg!(feature = "aliased-vars") {
             assert_eq!(id.to_string(), "λα.α");
         } else {
             assert_eq!(id.to_string(), "λ0.0");
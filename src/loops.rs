@@ -0,0 +1,245 @@
+//! Detection of reductions that never end: terms that come back to an
+//! earlier intermediate term, and terms that keep reproducing the redex that
+//! was just contracted, applied to the same arguments, while they grow, like
+//! `Θ` or `Y f`.
+
+use std::{
+    collections::{hash_map::RandomState, HashMap, VecDeque},
+    hash::BuildHasher,
+};
+
+use crate::{path::Step, AlphaTerm, Body, Outcome, Path, Term};
+
+/// Times that the same redex has to reproduce itself, on a growing term and
+/// applied to the same arguments, until the reduction is considered diverging
+pub const GROWTH_LIMIT: usize = 256;
+
+/// Nodes of the latest intermediate terms that are kept, to tell a cycle from
+/// a collision of hashes. The older terms are just remembered by their hash.
+pub const SEEN_NODES: usize = 1 << 20;
+
+/// A redex that's found inside its own contractum
+struct Pumping {
+    redex: Term,
+    /// What the redex was applied to, a recursion that terminates changes them
+    args: Vec<Term>,
+    size: usize,
+    times: usize,
+}
+
+pub(crate) struct LoopDetector {
+    hasher: RandomState,
+    /// The hashes of the intermediate terms, with the last step where they were seen
+    seen: HashMap<u64, usize>,
+    /// The latest intermediate terms with their steps, of `SEEN_NODES` nodes at most
+    latest: VecDeque<(usize, Term)>,
+    latest_nodes: usize,
+    pumping: Option<Pumping>,
+}
+
+/// Checks if `t` has a subterm alpha-equivalent to `sub`, returns the size of `t`
+fn find(t: &Term, sub: &Term, sub_size: usize, found: &mut bool) -> usize {
+    let size = match t.body.as_ref() {
+        Body::Var(..) | Body::Ref(..) => 1,
+        Body::App(m, n) => 1 + find(m, sub, sub_size, found) + find(n, sub, sub_size, found),
+        Body::Abs(_, m) => 1 + find(m, sub, sub_size, found),
+    };
+    *found = *found || (size == sub_size && t.alpha_eq(sub));
+    size
+}
+
+impl Pumping {
+    fn applied_to(&self, args: &[&Term]) -> bool {
+        self.args.len() == args.len() && self.args.iter().zip(args).all(|(a, b)| a.alpha_eq(b))
+    }
+}
+
+/// The arguments that the subterm at `at` is applied to, the last one first
+fn arguments<'a>(t: &'a Term, at: &Path) -> Vec<&'a Term> {
+    let funs = at.0.iter().rev().take_while(|s| **s == Step::Fun).count();
+    let spine = Path(at.0[..at.0.len() - funs].to_vec());
+    let mut t = t.subterm(&spine).expect("the redex isn't part of the term");
+    let mut args = Vec::with_capacity(funs);
+    for _ in 0..funs {
+        let Body::App(m, n) = t.body.as_ref() else {
+            unreachable!("a function isn't applied")
+        };
+        args.push(n);
+        t = m;
+    }
+    args
+}
+
+impl LoopDetector {
+    pub(crate) fn new(initial: &Term) -> Self {
+        let mut detector = Self {
+            hasher: RandomState::new(),
+            seen: HashMap::new(),
+            latest: VecDeque::new(),
+            latest_nodes: 0,
+            pumping: None,
+        };
+        let hash = detector.hasher.hash_one(AlphaTerm::from_ref(initial));
+        detector.remember(hash, initial, 0);
+        detector
+    }
+
+    fn remember(&mut self, hash: u64, t: &Term, steps: usize) {
+        self.seen.insert(hash, steps);
+        self.latest.push_back((steps, t.clone()));
+        self.latest_nodes += t.size();
+        while self.latest_nodes > SEEN_NODES {
+            let (_, old) = self.latest.pop_front().unwrap();
+            self.latest_nodes -= old.size();
+        }
+    }
+
+    /// The intermediate term of `step`, if it's still kept
+    fn kept(&self, step: usize) -> Option<&Term> {
+        let i = self.latest.binary_search_by_key(&step, |(s, _)| *s).ok()?;
+        Some(&self.latest[i].1)
+    }
+
+    /// Watches the term after the `steps`-th step, which contracted `redex`
+    pub(crate) fn watch(
+        &mut self,
+        t: &Term,
+        redex: Option<(Term, Path)>,
+        steps: usize,
+    ) -> Option<Outcome> {
+        let hash = self.hasher.hash_one(AlphaTerm::from_ref(t));
+        if let Some(&start) = self.seen.get(&hash) {
+            if self.kept(start).is_some_and(|seen| seen.alpha_eq(t)) {
+                return Some(Outcome::Cycle {
+                    start,
+                    period: steps - start,
+                });
+            }
+        }
+        self.remember(hash, t, steps);
+        let (redex, at) = redex?;
+        let contractum = t.subterm(&at)?;
+        let mut reproduced = false;
+        find(contractum, &redex, redex.size(), &mut reproduced);
+        if !reproduced {
+            return None;
+        }
+        let size = t.size();
+        let args = arguments(t, &at);
+        match &mut self.pumping {
+            Some(p) if p.redex.alpha_eq(&redex) && p.size < size && p.applied_to(&args) => {
+                p.size = size;
+                p.times += 1;
+                (p.times >= GROWTH_LIMIT).then_some(Outcome::Diverges)
+            }
+            _ => {
+                self.pumping = Some(Pumping {
+                    redex,
+                    args: args.into_iter().cloned().collect(),
+                    size,
+                    times: 1,
+                });
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoopDetector, GROWTH_LIMIT, SEEN_NODES};
+    use crate::{
+        assert_alpha_eq,
+        encoding::Encoding,
+        fixtures::{abs, app, omega, var},
+        strategy::{Applicative, Normal},
+        Fuel, Outcome, Term,
+    };
+
+    /// (λx y . y (x x y)) (λx y . y (x x y))
+    fn theta() -> Term {
+        let a = abs(0, abs(1, app(var(1), app(app(var(0), var(0)), var(1)))));
+        app(a.clone(), a)
+    }
+
+    #[test]
+    fn finds_cycles() {
+        let mut t = omega();
+        let res = t.normalize(&Normal, Fuel::steps(100).detecting_loops());
+        assert_eq!(
+            res.outcome,
+            Outcome::Cycle {
+                start: 0,
+                period: 1
+            }
+        );
+        assert_eq!(res.steps, 1);
+
+        // (λx . z) ((λx . x x) (λx . x x)) loops after its first step by applicative order
+        let mut t = app(abs(1, var(2)), omega());
+        let res = t.normalize(&Applicative, Fuel::steps(100).detecting_loops());
+        assert_eq!(
+            res.outcome,
+            Outcome::Cycle {
+                start: 0,
+                period: 1
+            }
+        );
+        let res = t.normalize(&Normal, Fuel::steps(100).detecting_loops());
+        assert_eq!(res.outcome, Outcome::Normal);
+        assert_alpha_eq!(t, var(2));
+    }
+
+    #[test]
+    fn finds_growth() {
+        let mut t = theta();
+        let res = t.normalize(&Normal, Fuel::steps(10_000).detecting_loops());
+        assert_eq!(res.outcome, Outcome::Diverges);
+        assert!(res.steps < 10_000);
+
+        // Θ (λf x . x) reproduces its redex, but stops
+        let mut t = app(theta(), abs(2, abs(3, var(3))));
+        let res = t.normalize(&Normal, Fuel::steps(10_000).detecting_loops());
+        assert_eq!(res.outcome, Outcome::Normal);
+        assert_alpha_eq!(t, abs(3, var(3)));
+    }
+
+    #[test]
+    fn long_recursions_stop() {
+        // Θ (λf n . n (λp . c (c (c (f p)))) z) on a Scott numeral, the term
+        // grows while `Θ` reproduces its redex more than `GROWTH_LIMIT` times
+        let n = GROWTH_LIMIT + 8;
+        let c = |t| app(var(5), t);
+        let g = abs(
+            2,
+            abs(
+                3,
+                app(app(var(3), abs(4, c(c(c(app(var(2), var(4))))))), var(6)),
+            ),
+        );
+        let mut t = app(app(theta(), g), Encoding::Scott.nat(n));
+        let res = t.normalize(&Normal, Fuel::steps(100_000).detecting_loops());
+        assert_eq!(res.outcome, Outcome::Normal);
+        assert_eq!(t.size(), 2 * 3 * n + 1);
+    }
+
+    #[test]
+    fn keeps_the_latest_terms() {
+        let mut detector = LoopDetector::new(&Term::church_nat(0));
+        for n in 1..2000 {
+            assert_eq!(detector.watch(&Term::church_nat(n), None, n), None);
+        }
+        assert!(detector.latest_nodes <= SEEN_NODES);
+        // the first numerals are just remembered by their hash, so they don't
+        // make a cycle, but the latest ones do
+        assert!(detector.kept(0).is_none());
+        assert_eq!(detector.watch(&Term::church_nat(0), None, 2000), None);
+        assert_eq!(
+            detector.watch(&Term::church_nat(1999), None, 2001),
+            Some(Outcome::Cycle {
+                start: 1999,
+                period: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Environment based abstract machines, where closures are evaluated without
+//! substitution: the Krivine machine for call-by-name and the CEK machine for
+//! call-by-value. Both read back the full normal form by evaluating under
+//! abstractions, applying them to fresh variables. References to global
+//! definitions are unfolded when they are applied, counting a step, or when
+//! they are read back and their definitions aren't normal.
+
+use crate::{
+    env::{extend, lookup, Counter, Env, Head},
+    DeBruijn, Fuel, Normalization, Outcome, Term,
+};
+
+#[derive(Clone)]
+enum KEntry<'a> {
+    Closure(&'a DeBruijn, Env<KEntry<'a>>),
+    Level(usize),
+}
+
+/// A weak head normal form of the Krivine machine
+enum KWhnf<'a> {
+    Lambda(&'a DeBruijn, Env<KEntry<'a>>),
+    /// The arguments are stored as a stack, so the first one is the last
+    Neutral(Head<'a>, Vec<KEntry<'a>>),
+}
+
+struct Krivine(Counter);
+
+impl<'a> Krivine {
+    fn whnf(&self, mut t: &'a DeBruijn, mut env: Env<KEntry<'a>>) -> Result<KWhnf<'a>, Outcome> {
+        let mut stack = Vec::new();
+        loop {
+            match t {
+                DeBruijn::App(m, n) => {
+                    stack.push(KEntry::Closure(n, env.clone()));
+                    t = m;
+                }
+                DeBruijn::Bound(i) => match lookup(&env, *i) {
+                    KEntry::Closure(nt, nenv) => (t, env) = (nt, nenv),
+                    KEntry::Level(l) => return Ok(KWhnf::Neutral(Head::Level(l), stack)),
+                },
+                DeBruijn::Free(v) => return Ok(KWhnf::Neutral(Head::Free(*v), stack)),
+                DeBruijn::Ref(r) if stack.is_empty() => {
+                    return Ok(KWhnf::Neutral(Head::Ref(r), stack))
+                }
+                DeBruijn::Ref(r) => {
+                    self.0.tick()?;
+                    (t, env) = (r.nameless(), None);
+                }
+                DeBruijn::Abs(b) => match stack.pop() {
+                    Some(arg) => {
+                        self.0.tick()?;
+                        (t, env) = (b, extend(env, arg));
+                    }
+                    None => return Ok(KWhnf::Lambda(b, env)),
+                },
+            }
+        }
+    }
+
+    fn quote(&self, v: KWhnf<'a>, depth: usize) -> Result<DeBruijn, Outcome> {
+        match v {
+            KWhnf::Lambda(b, env) => {
+                let body = self.whnf(b, extend(env, KEntry::Level(depth)))?;
+                let body = self.quote(body, depth + 1)?;
+                self.0.abs(body)
+            }
+            KWhnf::Neutral(Head::Ref(r), _) if !r.is_normal() => {
+                self.0.tick()?;
+                let v = self.whnf(r.nameless(), None)?;
+                self.quote(v, depth)
+            }
+            KWhnf::Neutral(h, args) => {
+                let mut t = self.0.head(h, depth)?;
+                for arg in args.into_iter().rev() {
+                    let arg = match arg {
+                        KEntry::Closure(at, aenv) => self.whnf(at, aenv)?,
+                        KEntry::Level(l) => KWhnf::Neutral(Head::Level(l), Vec::new()),
+                    };
+                    t = self.0.app(t, self.quote(arg, depth)?)?;
+                }
+                Ok(t)
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+enum CValue<'a> {
+    Closure(&'a DeBruijn, Env<CValue<'a>>),
+    Neutral(Head<'a>, Vec<CValue<'a>>),
+}
+
+/// The continuation of the CEK machine
+enum Kont<'a> {
+    /// Evaluate this argument, then apply the function to it
+    Arg(&'a DeBruijn, Env<CValue<'a>>),
+    /// Apply this function to the value
+    Fun(CValue<'a>),
+    /// Apply the value, an unfolded reference, to this argument
+    Unfolded(CValue<'a>),
+}
+
+struct Cek(Counter);
+
+impl<'a> Cek {
+    fn eval(&self, mut t: &'a DeBruijn, mut env: Env<CValue<'a>>) -> Result<CValue<'a>, Outcome> {
+        let mut stack = Vec::new();
+        'eval: loop {
+            let mut v = match t {
+                DeBruijn::App(m, n) => {
+                    stack.push(Kont::Arg(n, env.clone()));
+                    t = m;
+                    continue;
+                }
+                DeBruijn::Bound(i) => lookup(&env, *i),
+                DeBruijn::Free(v) => CValue::Neutral(Head::Free(*v), Vec::new()),
+                DeBruijn::Abs(b) => CValue::Closure(b, env.clone()),
+                DeBruijn::Ref(r) => CValue::Neutral(Head::Ref(r), Vec::new()),
+            };
+            while let Some(k) = stack.pop() {
+                match k {
+                    Kont::Arg(n, nenv) => {
+                        stack.push(Kont::Fun(v));
+                        (t, env) = (n, nenv);
+                        continue 'eval;
+                    }
+                    Kont::Fun(CValue::Closure(b, cenv)) => {
+                        self.0.tick()?;
+                        (t, env) = (b, extend(cenv, v));
+                        continue 'eval;
+                    }
+                    // an applied reference is unfolded, so it never has arguments
+                    Kont::Fun(CValue::Neutral(Head::Ref(r), _)) => {
+                        self.0.tick()?;
+                        stack.push(Kont::Unfolded(v));
+                        (t, env) = (r.nameless(), None);
+                        continue 'eval;
+                    }
+                    Kont::Fun(CValue::Neutral(h, mut args)) => {
+                        args.push(v);
+                        v = CValue::Neutral(h, args);
+                    }
+                    Kont::Unfolded(arg) => {
+                        stack.push(Kont::Fun(v));
+                        v = arg;
+                    }
+                }
+            }
+            return Ok(v);
+        }
+    }
+
+    fn quote(&self, v: CValue<'a>, depth: usize) -> Result<DeBruijn, Outcome> {
+        match v {
+            CValue::Closure(b, env) => {
+                let var = CValue::Neutral(Head::Level(depth), Vec::new());
+                let body = self.eval(b, extend(env, var))?;
+                let body = self.quote(body, depth + 1)?;
+                self.0.abs(body)
+            }
+            CValue::Neutral(Head::Ref(r), _) if !r.is_normal() => {
+                self.0.tick()?;
+                let v = self.eval(r.nameless(), None)?;
+                self.quote(v, depth)
+            }
+            CValue::Neutral(h, args) => {
+                let mut t = self.0.head(h, depth)?;
+                for arg in args {
+                    t = self.0.app(t, self.quote(arg, depth)?)?;
+                }
+                Ok(t)
+            }
+        }
+    }
+}
+
+impl Term {
+    /// Normalizes the term on the Krivine machine (call-by-name), reaching the
+    /// same normal form of `strategy::Normal`.
+    pub fn normalize_krivine(&mut self, fuel: Fuel) -> Normalization {
+        let t = DeBruijn::from(&*self);
+        let machine = Krivine(Counter::new(fuel));
+        let res = machine.whnf(&t, None).and_then(|v| machine.quote(v, 0));
+        machine.0.finish(self, res)
+    }
+
+    /// Normalizes the term on the CEK machine (call-by-value), reaching the
+    /// same normal form of `strategy::Applicative`.
+    pub fn normalize_cek(&mut self, fuel: Fuel) -> Normalization {
+        let t = DeBruijn::from(&*self);
+        let machine = Cek(Counter::new(fuel));
+        let res = machine.eval(&t, None).and_then(|v| machine.quote(v, 0));
+        machine.0.finish(self, res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assert_alpha_eq,
+        fixtures::{abs, app, omega, plus, var},
+        strategy::{Applicative, Normal},
+        Fuel, Outcome, Term,
+    };
+
+    #[test]
+    fn agrees_with_step_reducers() {
+        // λm n f . m (n f)
+        let mult = abs(0, abs(1, abs(2, app(var(0), app(var(1), var(2))))));
+        let t = app(
+            app(
+                mult,
+                app(app(plus(), Term::church_nat(2)), Term::church_nat(3)),
+            ),
+            Term::church_nat(4),
+        );
+
+        let mut krivine = t.clone();
+        assert_eq!(
+            krivine.normalize_krivine(Fuel::UNBOUNDED).outcome,
+            Outcome::Normal
+        );
+        let mut cek = t.clone();
+        assert_eq!(cek.normalize_cek(Fuel::UNBOUNDED).outcome, Outcome::Normal);
+
+        let mut normal = t.clone();
+        normal.normalize(&Normal, Fuel::UNBOUNDED);
+        let mut applicative = t;
+        applicative.normalize(&Applicative, Fuel::UNBOUNDED);
+
+        assert_eq!(krivine.as_church_nat(), Some(20));
+        assert_alpha_eq!(krivine, normal);
+        assert_alpha_eq!(cek, applicative);
+    }
+
+    #[test]
+    fn strictness() {
+        // (λx y . y) ((λx . x x) (λx . x x))
+        let t = app(abs(0, abs(1, var(1))), omega());
+
+        let mut krivine = t.clone();
+        assert_eq!(
+            krivine.normalize_krivine(Fuel::steps(100)).outcome,
+            Outcome::Normal
+        );
+        assert_alpha_eq!(krivine, abs(1, var(1)));
+
+        let mut cek = t.clone();
+        assert_eq!(
+            cek.normalize_cek(Fuel::steps(100)).outcome,
+            Outcome::OutOfFuel
+        );
+        assert_eq!(cek, t);
+    }
+
+    #[test]
+    fn bounds_the_normal_form() {
+        // (λf x . f (f x)) (λf x . f (f x)) ~> λf x . f (f (f (f x))), of size 11
+        let t = app(Term::church_nat(2), Term::church_nat(2));
+        let evaluators = [
+            Term::normalize_krivine,
+            Term::normalize_cek,
+            Term::normalize_by_need,
+            Term::normalize_nbe,
+        ];
+        for normalize in evaluators {
+            let mut nf = t.clone();
+            let res = normalize(&mut nf, Fuel::UNBOUNDED.with_size(10));
+            assert_eq!(res.outcome, Outcome::SizeExceeded);
+            assert_eq!(nf, t);
+            let res = normalize(&mut nf, Fuel::UNBOUNDED.with_size(11));
+            assert_eq!(res.outcome, Outcome::Normal);
+            assert_eq!(nf.as_church_nat(), Some(4));
+        }
+    }
+}
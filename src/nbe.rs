@@ -0,0 +1,171 @@
+//! Normalization by evaluation: terms are evaluated into Rust closures, so
+//! β-reduction is just a function call, and then quoted back to a normal form.
+//! Arguments are passed as thunks, evaluated when they are first needed.
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    env::{extend, lookup, Counter, Env, Head},
+    DeBruijn, Fuel, Normalization, Outcome, Term,
+};
+
+type Fun<'a> = Rc<dyn Fn(Arg<'a>) -> Result<Value<'a>, Outcome> + 'a>;
+
+/// An argument that's evaluated at most once, when it's first needed
+type Arg<'a> = Rc<RefCell<Thunk<'a>>>;
+
+enum Thunk<'a> {
+    Delayed(&'a DeBruijn, Env<Arg<'a>>),
+    Forced(Value<'a>),
+}
+
+#[derive(Clone)]
+enum Value<'a> {
+    Lam(Fun<'a>),
+    Neutral(Rc<Neutral<'a>>),
+}
+
+enum Neutral<'a> {
+    Head(Head<'a>),
+    App(Rc<Neutral<'a>>, Arg<'a>),
+}
+
+impl<'a> Value<'a> {
+    fn head(h: Head<'a>) -> Self {
+        Self::Neutral(Neutral::Head(h).into())
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Evaluator<'a> {
+    counter: &'a Counter,
+}
+
+impl<'a> Evaluator<'a> {
+    fn eval(self, t: &'a DeBruijn, env: Env<Arg<'a>>) -> Result<Value<'a>, Outcome> {
+        match t {
+            DeBruijn::Bound(i) => self.force(&lookup(&env, *i)),
+            DeBruijn::Free(v) => Ok(Value::head(Head::Free(*v))),
+            DeBruijn::Ref(r) => Ok(Value::head(Head::Ref(r))),
+            DeBruijn::Abs(b) => Ok(Value::Lam(Rc::new(move |arg| {
+                self.eval(b, extend(env.clone(), arg))
+            }))),
+            DeBruijn::App(m, n) => {
+                let m = self.eval(m, env.clone())?;
+                self.apply(m, Rc::new(RefCell::new(Thunk::Delayed(n, env))))
+            }
+        }
+    }
+
+    fn force(self, arg: &Arg<'a>) -> Result<Value<'a>, Outcome> {
+        let (t, env) = match &*arg.borrow() {
+            Thunk::Forced(v) => return Ok(v.clone()),
+            Thunk::Delayed(t, env) => (*t, env.clone()),
+        };
+        let v = self.eval(t, env)?;
+        *arg.borrow_mut() = Thunk::Forced(v.clone());
+        Ok(v)
+    }
+
+    fn apply(self, m: Value<'a>, n: Arg<'a>) -> Result<Value<'a>, Outcome> {
+        match m {
+            Value::Lam(f) => {
+                self.counter.tick()?;
+                f(n)
+            }
+            Value::Neutral(h) => match *h {
+                Neutral::Head(Head::Ref(r)) => {
+                    self.counter.tick()?;
+                    let f = self.eval(r.nameless(), None)?;
+                    self.apply(f, n)
+                }
+                _ => Ok(Value::Neutral(Neutral::App(h, n).into())),
+            },
+        }
+    }
+
+    fn quote(self, v: Value<'a>, depth: usize) -> Result<DeBruijn, Outcome> {
+        match v {
+            Value::Lam(f) => {
+                let var = Thunk::Forced(Value::head(Head::Level(depth)));
+                let body = f(Rc::new(RefCell::new(var)))?;
+                self.counter.abs(self.quote(body, depth + 1)?)
+            }
+            Value::Neutral(n) => self.quote_neutral(&n, depth),
+        }
+    }
+
+    fn quote_neutral(self, n: &Neutral<'a>, depth: usize) -> Result<DeBruijn, Outcome> {
+        match n {
+            Neutral::Head(Head::Ref(r)) if !r.is_normal() => {
+                self.counter.tick()?;
+                self.quote(self.eval(r.nameless(), None)?, depth)
+            }
+            Neutral::Head(h) => self.counter.head(*h, depth),
+            Neutral::App(m, n) => self.counter.app(
+                self.quote_neutral(m, depth)?,
+                self.quote(self.force(n)?, depth)?,
+            ),
+        }
+    }
+}
+
+impl Term {
+    /// Normalizes the term by evaluation, the fastest way of getting its
+    /// β-normal form. Arguments are evaluated by need, so it reaches the normal
+    /// form whenever there's one, like `strategy::Normal`.
+    pub fn normalize_nbe(&mut self, fuel: Fuel) -> Normalization {
+        let t = DeBruijn::from(&*self);
+        let counter = Counter::new(fuel);
+        let evaluator = Evaluator { counter: &counter };
+        let res = evaluator.eval(&t, None).and_then(|v| evaluator.quote(v, 0));
+        counter.finish(self, res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assert_alpha_eq,
+        fixtures::{abs, app, omega, plus, var},
+        strategy::Normal,
+        Fuel, Outcome,
+    };
+
+    #[test]
+    fn agrees_with_normal_order() {
+        // λf x . f (f x)
+        let two = abs(4, abs(5, app(var(4), app(var(4), var(5)))));
+        let terms = [
+            app(app(plus(), two.clone()), two.clone()),
+            app(plus(), two.clone()),
+            app(app(two.clone(), two.clone()), var(6)),
+            abs(7, app(var(7), app(two, var(7)))),
+        ];
+        for t in terms {
+            let mut nbe = t.clone();
+            assert_eq!(nbe.normalize_nbe(Fuel::UNBOUNDED).outcome, Outcome::Normal);
+            let mut normal = t;
+            normal.normalize(&Normal, Fuel::UNBOUNDED);
+            assert_alpha_eq!(nbe, normal);
+        }
+    }
+
+    #[test]
+    fn runs_out_of_fuel() {
+        let mut t = omega();
+        let res = t.normalize_nbe(Fuel::steps(100));
+        assert_eq!(res.outcome, Outcome::OutOfFuel);
+        assert_eq!(res.steps, 100);
+        assert_eq!(t, omega());
+    }
+
+    #[test]
+    fn lazy_arguments() {
+        // (λx y . y) ((λx . x x) (λx . x x))
+        let mut t = app(abs(0, abs(1, var(1))), omega());
+        let res = t.normalize_nbe(Fuel::steps(100));
+        assert_eq!((res.outcome, res.steps), (Outcome::Normal, 1));
+        assert_alpha_eq!(t, abs(1, var(1)));
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    error::Error,
+    fmt,
+    iter::Peekable,
+    str::{CharIndices, FromStr},
+};
+
+use crate::{alias::Naming, Body, Term};
+
+/// Where and what the parser expected, but didn't find
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseTermError {
+    /// Byte offset in the source
+    pub pos: usize,
+    pub expected: &'static str,
+}
+
+impl fmt::Display for ParseTermError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {} at {}", self.expected, self.pos)
+    }
+}
+
+impl Error for ParseTermError {}
+
+type Result<T> = std::result::Result<T, ParseTermError>;
+
+struct Parser<'a> {
+    src: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn pos(&mut self) -> usize {
+        self.chars.peek().map_or(self.src.len(), |(i, _)| *i)
+    }
+
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<()> {
+        if self.peek() == Some(c) {
+            self.chars.next();
+            Ok(())
+        } else {
+            self.fail(expected)
+        }
+    }
+
+    fn fail<T>(&mut self, expected: &'static str) -> Result<T> {
+        Err(ParseTermError {
+            pos: self.pos(),
+            expected,
+        })
+    }
+
+    fn var(&mut self) -> Result<usize> {
+        self.peek();
+        let start = self.pos();
+        while self
+            .chars
+            .next_if(|(_, c)| Naming::DISPLAY.is_name_char(*c))
+            .is_some()
+        {}
+        let end = self.pos();
+        Naming::DISPLAY
+            .index(&self.src[start..end])
+            .ok_or(ParseTermError {
+                pos: start,
+                expected: "a variable",
+            })
+    }
+
+    /// `λv.M`, `M N` or a variable
+    fn term(&mut self) -> Result<Term> {
+        let mut t: Option<Term> = None;
+        loop {
+            let arg = match self.peek() {
+                Some('λ' | '\\') => {
+                    self.chars.next();
+                    let v = self.var()?;
+                    self.expect('.', "`.`")?;
+                    // an abstraction extends to the right, so it's the last argument
+                    let abs = Body::Abs(v, self.term()?).into();
+                    return Ok(match t {
+                        Some(m) => Body::App(m, abs).into(),
+                        None => abs,
+                    });
+                }
+                Some('(') => {
+                    self.chars.next();
+                    let inner = self.term()?;
+                    self.expect(')', "`)`")?;
+                    inner
+                }
+                Some(c) if Naming::DISPLAY.is_name_char(c) => Body::Var(self.var()?).into(),
+                _ => break,
+            };
+            t = Some(match t {
+                Some(m) => Body::App(m, arg).into(),
+                None => arg,
+            });
+        }
+        t.map_or_else(|| self.fail("a term"), Ok)
+    }
+}
+
+/// Reads the output of `Term`'s `Display`, also accepting `\` for `λ`
+impl FromStr for Term {
+    type Err = ParseTermError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            src: s,
+            chars: s.char_indices().peekable(),
+        };
+        let t = parser.term()?;
+        match parser.peek() {
+            None => Ok(t),
+            Some(_) => parser.fail("the end"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseTermError;
+    use crate::{alias::Naming, assert_alpha_eq, Body, Term};
+
+    /// Xorshift, just enough to generate terms without more dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        /// Few variables, so there's shadowing and free variables
+        fn term(&mut self, depth: usize) -> Term {
+            let var = self.below(4) as usize;
+            match if depth == 0 { 0 } else { self.below(3) } {
+                0 => Body::Var(var).into(),
+                1 => Body::App(self.term(depth - 1), self.term(depth - 1)).into(),
+                _ => Body::Abs(var, self.term(depth - 1)).into(),
+            }
+        }
+    }
+
+    /// Renames the single digit variables of `src` as `Naming::DISPLAY` does
+    fn with_names(src: &str) -> String {
+        src.chars()
+            .map(|c| match c.to_digit(10) {
+                Some(d) => Naming::DISPLAY.name(d as usize),
+                None => c.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn minimal_parentheses() {
+        let cases = [
+            "(λ0.0 0) (1 2) λ3.3",
+            "0 (λ1.1) 2",
+            "0 1 2",
+            "λ0.λ1.0 (1 λ2.2)",
+        ];
+        for src in cases {
+            let src = with_names(src);
+            let t: Term = src.parse().unwrap();
+            assert_eq!(t.to_string(), src);
+        }
+        let t: Term = with_names(r"(\0. 0)(\1.1)").parse().unwrap();
+        assert_eq!(t.to_string(), with_names("(λ0.0) λ1.1"));
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src: &str| src.parse::<Term>().unwrap_err();
+        let unclosed = with_names("(0 1");
+        assert_eq!(
+            err(&unclosed),
+            ParseTermError {
+                pos: unclosed.len(),
+                expected: "`)`"
+            }
+        );
+        assert_eq!(err(&with_names("λ0 0")).expected, "`.`");
+        assert_eq!(err(&with_names("0 )")).expected, "the end");
+        assert_eq!(err("").expected, "a term");
+        assert_eq!(err("λ..x").expected, "a variable");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..1_000 {
+            let t = rng.term(6);
+            let src = t.to_string();
+            let parsed: Term = src.parse().unwrap_or_else(|e| panic!("{src}: {e}"));
+            assert_alpha_eq!(parsed, t, "{src} was read as {parsed}");
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+
+use crate::{Body, Term};
+
+/// A move from a term to one of its immediate subterms
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Step {
+    /// `M` of `M N`
+    Fun,
+    /// `N` of `M N`
+    Arg,
+    /// `M` of `λx.M`
+    Body,
+}
+
+/// Position of a subterm, as the steps to reach it from the root
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+pub struct Path(pub Vec<Step>);
+
+impl Path {
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn join(&self, step: Step) -> Self {
+        let mut path = self.clone();
+        path.0.push(step);
+        path
+    }
+
+    /// Enters `step` while `search` runs, leaving it when nothing was found
+    pub fn search(&mut self, step: Step, search: impl FnOnce(&mut Self) -> bool) -> bool {
+        self.0.push(step);
+        let found = search(self);
+        if !found {
+            self.0.pop();
+        }
+        found
+    }
+}
+
+/// Writes the steps as `f`, `a` and `b` (function, argument and body), and the root as `ε`
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("ε");
+        }
+        for step in &self.0 {
+            f.write_str(match step {
+                Step::Fun => "f",
+                Step::Arg => "a",
+                Step::Body => "b",
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Term {
+    pub fn subterm(&self, path: &Path) -> Option<&Self> {
+        path.0
+            .iter()
+            .try_fold(self, |t, step| match (t.body.as_ref(), step) {
+                (Body::App(m, _), Step::Fun) => Some(m),
+                (Body::App(_, n), Step::Arg) => Some(n),
+                (Body::Abs(_, m), Step::Body) => Some(m),
+                _ => None,
+            })
+    }
+
+    pub fn subterm_mut(&mut self, path: &Path) -> Option<&mut Self> {
+        path.0
+            .iter()
+            .try_fold(self, |t, step| match (t.body.as_mut(), step) {
+                (Body::App(m, _), Step::Fun) => Some(m),
+                (Body::App(_, n), Step::Arg) => Some(n),
+                (Body::Abs(_, m), Step::Body) => Some(m),
+                _ => None,
+            })
+    }
+
+    pub fn is_redex(&self) -> bool {
+        matches!(self.body.as_ref(), Body::App(m, _) if matches!(m.body.as_ref(), Body::Abs(..) | Body::Ref(..)))
+    }
+
+    /// If it's a reference to a definition that isn't normal, which is a redex
+    /// for the strong strategies even when it isn't applied
+    pub fn is_delta_redex(&self) -> bool {
+        matches!(self.body.as_ref(), Body::Ref(r) if !r.is_normal())
+    }
+
+    pub fn is_eta_redex(&self) -> bool {
+        match self.body.as_ref() {
+            Body::Abs(v, b) => matches!(
+                b.body.as_ref(),
+                Body::App(m, n) if *n.body == Body::Var(*v) && !m.free_vars().contains(v)
+            ),
+            _ => false,
+        }
+    }
+
+    /// Positions of all β-redexes and δ-redexes, from the leftmost outermost to the rightmost innermost
+    pub fn redexes(&self) -> Vec<Path> {
+        let mut redexes = Vec::new();
+        self.redexes_from(&mut Path::root(), &mut redexes);
+        redexes
+    }
+
+    fn redexes_from(&self, at: &mut Path, redexes: &mut Vec<Path>) {
+        if self.is_redex() || self.is_delta_redex() {
+            redexes.push(at.clone());
+        }
+        match self.body.as_ref() {
+            Body::Var(..) | Body::Ref(..) => (),
+            Body::App(m, n) => {
+                at.0.push(Step::Fun);
+                m.redexes_from(at, redexes);
+                at.0.pop();
+                at.0.push(Step::Arg);
+                n.redexes_from(at, redexes);
+                at.0.pop();
+            }
+            Body::Abs(_, m) => {
+                at.0.push(Step::Body);
+                m.redexes_from(at, redexes);
+                at.0.pop();
+            }
+        }
+    }
+
+    /// Contracts the redex at `path`, returns a `bool` indicating if there was a redex there
+    pub fn contract_at(&mut self, path: &Path) -> bool {
+        self.subterm_mut(path).is_some_and(Term::contract)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Path, Step};
+    use crate::{
+        assert_alpha_eq,
+        fixtures::{abs, app, var},
+    };
+
+    #[test]
+    fn enumerate_redexes() {
+        // (λx . (λy . y) x) ((λz . z) w)
+        let id = |v| abs(v, var(v));
+        let t = app(abs(0, app(id(1), var(0))), app(id(2), var(3)));
+        let redexes = t.redexes();
+        assert_eq!(
+            redexes,
+            [
+                Path::root(),
+                Path(vec![Step::Fun, Step::Body]),
+                Path(vec![Step::Arg]),
+            ]
+        );
+        assert_eq!(
+            redexes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["ε", "fb", "a"]
+        );
+        assert_eq!(t.subterm(&redexes[2]), Some(&app(id(2), var(3))));
+    }
+
+    #[test]
+    fn contract_chosen_redex() {
+        // (λx . (λy . y) x) ((λz . z) w)
+        let id = |v| abs(v, var(v));
+        let mut t = app(abs(0, app(id(1), var(0))), app(id(2), var(3)));
+        assert!(!t.contract_at(&Path(vec![Step::Fun])));
+        assert!(t.contract_at(&Path(vec![Step::Arg])));
+        assert_alpha_eq!(t, app(abs(0, app(id(1), var(0))), var(3)));
+        assert!(t.contract_at(&Path(vec![Step::Fun, Step::Body])));
+        assert_alpha_eq!(t, app(id(0), var(3)));
+        assert_eq!(t.redexes(), [Path::root()]);
+    }
+}
@@ -0,0 +1,266 @@
+//! Combinatory logic: lambda terms are translated to combinator expressions by
+//! bracket abstraction, which removes every binder, and reduced weakly, by the
+//! rewriting rules of each combinator.
+
+use std::fmt;
+
+use crate::{term::write_alias, Body, Fuel, Normalization, Outcome, Term};
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Comb {
+    /// `S x y z = x z (y z)`
+    S,
+    /// `K x y = x`
+    K,
+    /// `I x = x`
+    I,
+    /// `B x y z = x (y z)`
+    B,
+    /// `C x y z = x z y`
+    C,
+    Var(usize),
+    App(Box<Comb>, Box<Comb>),
+}
+
+/// How a binder is removed from its body
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Abstraction {
+    /// Just `S`, `K` and `I`, each application becomes an `S`
+    Naive,
+    /// Turner's rules, with `B` and `C` for the applications where just one
+    /// side uses the variable, and η-reductions
+    Turner,
+}
+
+fn app(m: Comb, n: Comb) -> Comb {
+    Comb::App(m.into(), n.into())
+}
+
+impl Comb {
+    pub fn from_term(t: &Term, abstraction: Abstraction) -> Self {
+        match t.body.as_ref() {
+            Body::Var(v) => Self::Var(*v),
+            Body::App(m, n) => app(
+                Self::from_term(m, abstraction),
+                Self::from_term(n, abstraction),
+            ),
+            Body::Abs(v, m) => Self::from_term(m, abstraction).abstract_var(*v, abstraction),
+            Body::Ref(..) => Self::from_term(&t.inline_refs(), abstraction),
+        }
+    }
+
+    pub fn has_var(&self, var: usize) -> bool {
+        match self {
+            Self::Var(v) => *v == var,
+            Self::App(m, n) => m.has_var(var) || n.has_var(var),
+            _ => false,
+        }
+    }
+
+    /// `[var] self`, a combinator expression without `var` such that
+    /// `[var] self var` reduces to `self`
+    pub fn abstract_var(self, var: usize, abstraction: Abstraction) -> Self {
+        if self == Self::Var(var) {
+            return Self::I;
+        }
+        let Self::App(m, n) = self else {
+            return app(Self::K, self);
+        };
+        if abstraction == Abstraction::Naive {
+            let m = m.abstract_var(var, abstraction);
+            let n = n.abstract_var(var, abstraction);
+            return app(app(Self::S, m), n);
+        }
+        match (m.has_var(var), n.has_var(var)) {
+            (false, false) => app(Self::K, Self::App(m, n)),
+            (false, true) if *n == Self::Var(var) => *m,
+            (false, true) => app(app(Self::B, *m), n.abstract_var(var, abstraction)),
+            (true, false) => app(app(Self::C, m.abstract_var(var, abstraction)), *n),
+            (true, true) => {
+                let m = m.abstract_var(var, abstraction);
+                let n = n.abstract_var(var, abstraction);
+                app(app(Self::S, m), n)
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Self::App(m, n) => 1 + m.size() + n.size(),
+            _ => 1,
+        }
+    }
+
+    /// Rewrites the expression if it's a combinator applied to all of its arguments,
+    /// returns a `bool` indicating if it was rewritten
+    pub fn contract(&mut self) -> bool {
+        let mut args = Vec::new();
+        let mut head = &*self;
+        while let Self::App(m, n) = head {
+            args.push(n.as_ref());
+            head = m;
+        }
+        let arity = match head {
+            Self::I => 1,
+            Self::K => 2,
+            Self::S | Self::B | Self::C => 3,
+            Self::Var(..) | Self::App(..) => return false,
+        };
+        if args.len() != arity {
+            return false;
+        }
+        args.reverse();
+        let x = args[0].clone();
+        let arg = |i: usize| args[i].clone();
+        *self = match head {
+            Self::I | Self::K => x,
+            Self::S => app(app(x, arg(2)), app(arg(1), arg(2))),
+            Self::B => app(x, app(arg(1), arg(2))),
+            Self::C => app(app(x, arg(2)), arg(1)),
+            Self::Var(..) | Self::App(..) => unreachable!(),
+        };
+        true
+    }
+
+    /// Contracts the leftmost outermost combinator,
+    /// returns a `bool` indicating if it's irreducible.
+    pub fn step(&mut self) -> bool {
+        if self.contract() {
+            return false;
+        }
+        match self {
+            Self::App(m, n) => m.step() && n.step(),
+            _ => true,
+        }
+    }
+
+    /// Reduces weakly until it's irreducible or exhausts the `fuel`,
+    /// loops aren't detected
+    pub fn normalize(&mut self, fuel: Fuel) -> Normalization {
+        let mut steps = 0;
+        let outcome = loop {
+            if let Err(outcome) = fuel.check(steps) {
+                break outcome;
+            }
+            if self.step() {
+                break Outcome::Normal;
+            }
+            steps += 1;
+            if fuel.size != usize::MAX && self.size() > fuel.size {
+                break Outcome::SizeExceeded;
+            }
+        };
+        Normalization { outcome, steps }
+    }
+
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, paren_app: bool) -> fmt::Result {
+        match self {
+            Self::S => f.write_str("S"),
+            Self::K => f.write_str("K"),
+            Self::I => f.write_str("I"),
+            Self::B => f.write_str("B"),
+            Self::C => f.write_str("C"),
+            Self::Var(v) => write_alias(*v, f),
+            Self::App(m, n) => {
+                if paren_app {
+                    f.write_str("(")?;
+                }
+                m.fmt_with(f, false)?;
+                f.write_str(" ")?;
+                n.fmt_with(f, true)?;
+                if paren_app {
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn max_var(&self) -> Option<usize> {
+        match self {
+            Self::Var(v) => Some(*v),
+            Self::App(m, n) => m.max_var().max(n.max_var()),
+            _ => None,
+        }
+    }
+
+    fn to_term(&self, fresh: usize) -> Term {
+        let var = |i: usize| Term::from(Body::Var(fresh + i));
+        let abs = |i: usize, m: Term| Term::from(Body::Abs(fresh + i, m));
+        let tapp = |m: Term, n: Term| Term::from(Body::App(m, n));
+        match self {
+            Self::S => abs(
+                0,
+                abs(1, abs(2, tapp(tapp(var(0), var(2)), tapp(var(1), var(2))))),
+            ),
+            Self::K => abs(0, abs(1, var(0))),
+            Self::I => abs(0, var(0)),
+            Self::B => abs(0, abs(1, abs(2, tapp(var(0), tapp(var(1), var(2)))))),
+            Self::C => abs(0, abs(1, abs(2, tapp(tapp(var(0), var(2)), var(1))))),
+            Self::Var(v) => Body::Var(*v).into(),
+            Self::App(m, n) => tapp(m.to_term(fresh), n.to_term(fresh)),
+        }
+    }
+}
+
+/// Writes just the needed parentheses, like `S (K x) I`
+impl fmt::Display for Comb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, false)
+    }
+}
+
+/// Replaces each combinator by its lambda term, with binders that
+/// don't clash with the variables of the expression
+impl From<&Comb> for Term {
+    fn from(value: &Comb) -> Self {
+        value.to_term(value.max_var().map_or(0, |v| v + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Abstraction, Comb};
+    use crate::{
+        assert_alpha_eq,
+        fixtures::{abs, app, plus, var},
+        strategy::Normal,
+        Fuel, Outcome, Term,
+    };
+
+    #[test]
+    fn bracket_abstraction() {
+        // λx y . y x
+        let t = abs(0, abs(1, app(var(1), var(0))));
+        let naive = Comb::from_term(&t, Abstraction::Naive);
+        assert_eq!(naive.to_string(), "S (S (K S) (K I)) (S (K K) I)");
+        let turner = Comb::from_term(&t, Abstraction::Turner);
+        assert_eq!(turner.to_string(), "C I");
+        assert!(turner.size() < naive.size());
+
+        for mut c in [naive, turner] {
+            c = super::app(super::app(c, Comb::Var(2)), Comb::Var(3));
+            assert_eq!(c.normalize(Fuel::UNBOUNDED).outcome, Outcome::Normal);
+            assert_eq!(c, super::app(Comb::Var(3), Comb::Var(2)));
+        }
+    }
+
+    #[test]
+    fn agrees_with_beta_reduction() {
+        let t = app(app(plus(), Term::church_nat(2)), Term::church_nat(3));
+        let mut normal = t.clone();
+        normal.normalize(&Normal, Fuel::UNBOUNDED);
+        for abstraction in [Abstraction::Naive, Abstraction::Turner] {
+            // weak reduction needs the arguments to go under the numeral
+            let c = Comb::from_term(&app(app(t.clone(), var(8)), var(9)), abstraction);
+            let mut reduced = c.clone();
+            assert_eq!(reduced.normalize(Fuel::UNBOUNDED).outcome, Outcome::Normal);
+            let expected = (0..5).fold(Comb::Var(9), |ac, _| super::app(Comb::Var(8), ac));
+            assert_eq!(reduced, expected);
+
+            let mut back = Term::from(&Comb::from_term(&t, abstraction));
+            back.normalize_nbe(Fuel::UNBOUNDED);
+            assert_alpha_eq!(back, normal);
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use crate::{path::Step, Body, Path, Term};
+
+/// An order of choosing which β-redex is contracted next
+pub trait ReductionStrategy {
+    /// Name used to select the strategy, like `normal` or `weak-call-by-value`
+    fn name(&self) -> &'static str;
+
+    /// Position of the redex that's contracted by the next step, if any
+    fn next_redex(&self, t: &Term) -> Option<Path>;
+
+    /// Contracts the next redex of the term,
+    /// returns a `bool` indicating if it's irreducible under this strategy.
+    fn step(&self, t: &mut Term) -> bool {
+        self.next_redex(t).is_none_or(|p| !t.contract_at(&p))
+    }
+}
+
+/// Leftmost outermost redex first, finds the normal form whenever it exists
+pub struct Normal;
+
+/// Arguments before functions, and always the innermost redex (rightmost innermost)
+pub struct Applicative;
+
+/// Just the head redex, without going under abstractions, until a weak head normal form
+pub struct CallByName;
+
+/// Functions and their arguments before the application, without going under
+/// abstractions, until a weak normal form
+pub struct CallByValue;
+
+/// Just the head redex, going under abstractions, until a head normal form
+pub struct Head;
+
+/// The leftmost redex that has no redexes inside it
+pub struct LeftmostInnermost;
+
+/// Just the η-redexes, leftmost outermost first
+pub struct Eta;
+
+/// Reduces with `S` until it's stuck, then η-reduces,
+/// reaching the βη-normal form when `S` reaches the β-normal one.
+pub struct WithEta<S>(pub S);
+
+pub const STRATEGIES: &[&dyn ReductionStrategy] = &[
+    &Normal,
+    &Applicative,
+    &CallByName,
+    &CallByValue,
+    &Head,
+    &LeftmostInnermost,
+];
+
+pub fn by_name(name: &str) -> Option<&'static dyn ReductionStrategy> {
+    STRATEGIES.iter().copied().find(|s| s.name() == name)
+}
+
+/// Runs a search that appends to `at` the steps to the redex found, if any
+fn locate(t: &Term, search: impl Fn(&Term, &mut Path) -> bool) -> Option<Path> {
+    let mut at = Path::root();
+    search(t, &mut at).then_some(at)
+}
+
+impl<S: ReductionStrategy + ?Sized> ReductionStrategy for &S {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        (**self).next_redex(t)
+    }
+
+    fn step(&self, t: &mut Term) -> bool {
+        (**self).step(t)
+    }
+}
+
+impl Normal {
+    fn search(t: &Term, at: &mut Path) -> bool {
+        t.is_redex()
+            || match t.body.as_ref() {
+                Body::App(m, n) => {
+                    at.search(Step::Fun, |at| Self::search(m, at))
+                        || at.search(Step::Arg, |at| Self::search(n, at))
+                }
+                Body::Abs(_, m) => at.search(Step::Body, |at| Self::search(m, at)),
+                Body::Var(..) => false,
+                Body::Ref(..) => t.is_delta_redex(),
+            }
+    }
+}
+
+impl ReductionStrategy for Normal {
+    fn name(&self) -> &'static str {
+        "normal"
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        locate(t, Self::search)
+    }
+
+    fn step(&self, t: &mut Term) -> bool {
+        t.normal_beta_redex_step()
+    }
+}
+
+impl Applicative {
+    fn search(t: &Term, at: &mut Path) -> bool {
+        match t.body.as_ref() {
+            Body::App(m, n) => {
+                at.search(Step::Arg, |at| Self::search(n, at))
+                    || at.search(Step::Fun, |at| Self::search(m, at))
+                    || t.is_redex()
+            }
+            Body::Abs(_, m) => at.search(Step::Body, |at| Self::search(m, at)),
+            Body::Var(..) => false,
+            Body::Ref(..) => t.is_delta_redex(),
+        }
+    }
+}
+
+impl ReductionStrategy for Applicative {
+    fn name(&self) -> &'static str {
+        "applicative"
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        locate(t, Self::search)
+    }
+
+    fn step(&self, t: &mut Term) -> bool {
+        t.cbv_beta_redex_step()
+    }
+}
+
+impl CallByName {
+    fn search(t: &Term, at: &mut Path) -> bool {
+        t.is_redex()
+            || match t.body.as_ref() {
+                Body::App(m, _) => at.search(Step::Fun, |at| Self::search(m, at)),
+                Body::Abs(..) | Body::Var(..) | Body::Ref(..) => false,
+            }
+    }
+}
+
+impl ReductionStrategy for CallByName {
+    fn name(&self) -> &'static str {
+        "call-by-name"
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        locate(t, Self::search)
+    }
+}
+
+impl CallByValue {
+    fn search(t: &Term, at: &mut Path) -> bool {
+        match t.body.as_ref() {
+            Body::App(m, n) => {
+                at.search(Step::Fun, |at| Self::search(m, at))
+                    || at.search(Step::Arg, |at| Self::search(n, at))
+                    || t.is_redex()
+            }
+            Body::Abs(..) | Body::Var(..) | Body::Ref(..) => false,
+        }
+    }
+}
+
+impl ReductionStrategy for CallByValue {
+    fn name(&self) -> &'static str {
+        "weak-call-by-value"
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        locate(t, Self::search)
+    }
+}
+
+impl Head {
+    fn search(t: &Term, at: &mut Path) -> bool {
+        t.is_redex()
+            || match t.body.as_ref() {
+                Body::App(m, _) => at.search(Step::Fun, |at| Self::search(m, at)),
+                Body::Abs(_, m) => at.search(Step::Body, |at| Self::search(m, at)),
+                Body::Var(..) => false,
+                Body::Ref(..) => t.is_delta_redex(),
+            }
+    }
+}
+
+impl ReductionStrategy for Head {
+    fn name(&self) -> &'static str {
+        "head"
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        locate(t, Self::search)
+    }
+}
+
+impl LeftmostInnermost {
+    fn search(t: &Term, at: &mut Path) -> bool {
+        match t.body.as_ref() {
+            Body::App(m, n) => {
+                at.search(Step::Fun, |at| Self::search(m, at))
+                    || at.search(Step::Arg, |at| Self::search(n, at))
+                    || t.is_redex()
+            }
+            Body::Abs(_, m) => at.search(Step::Body, |at| Self::search(m, at)),
+            Body::Var(..) => false,
+            Body::Ref(..) => t.is_delta_redex(),
+        }
+    }
+}
+
+impl ReductionStrategy for LeftmostInnermost {
+    fn name(&self) -> &'static str {
+        "leftmost-innermost"
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        locate(t, Self::search)
+    }
+}
+
+impl Eta {
+    fn search(t: &Term, at: &mut Path) -> bool {
+        t.is_eta_redex()
+            || match t.body.as_ref() {
+                Body::App(m, n) => {
+                    at.search(Step::Fun, |at| Self::search(m, at))
+                        || at.search(Step::Arg, |at| Self::search(n, at))
+                }
+                Body::Abs(_, m) => at.search(Step::Body, |at| Self::search(m, at)),
+                Body::Var(..) | Body::Ref(..) => false,
+            }
+    }
+}
+
+impl ReductionStrategy for Eta {
+    fn name(&self) -> &'static str {
+        "eta"
+    }
+
+    /// Position of the next η-redex
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        locate(t, Self::search)
+    }
+
+    fn step(&self, t: &mut Term) -> bool {
+        t.eta_redex_step()
+    }
+}
+
+impl<S: ReductionStrategy> ReductionStrategy for WithEta<S> {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    /// Position of the next β-redex, or of the next η-redex when there isn't one
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        self.0.next_redex(t).or_else(|| Eta.next_redex(t))
+    }
+
+    fn step(&self, t: &mut Term) -> bool {
+        self.0.step(t) && t.eta_redex_step()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{by_name, Eta, Normal, ReductionStrategy, WithEta, STRATEGIES};
+    use crate::{
+        assert_alpha_eq,
+        fixtures::{abs, app, omega, var},
+        Fuel, Outcome, Term,
+    };
+
+    fn run(name: &str, mut t: Term) -> Term {
+        let res = t.normalize(by_name(name).unwrap(), Fuel::steps(100));
+        assert_eq!(res.outcome, Outcome::Normal, "{name} didn't stop on {t}");
+        t
+    }
+
+    #[test]
+    fn names_are_unique() {
+        for s in STRATEGIES {
+            assert_eq!(by_name(s.name()).unwrap().name(), s.name());
+        }
+    }
+
+    #[test]
+    fn stop_points() {
+        // λy . (λx . x) y
+        let t = abs(1, app(abs(0, var(0)), var(1)));
+        assert_alpha_eq!(run("call-by-name", t.clone()), t);
+        assert_alpha_eq!(run("weak-call-by-value", t.clone()), t);
+        assert_alpha_eq!(run("head", t.clone()), abs(1, var(1)));
+
+        // z ((λx . x) y)
+        let t = app(var(2), app(abs(0, var(0)), var(1)));
+        assert_alpha_eq!(run("call-by-name", t.clone()), t);
+        assert_alpha_eq!(run("head", t.clone()), t);
+        for name in [
+            "normal",
+            "applicative",
+            "weak-call-by-value",
+            "leftmost-innermost",
+        ] {
+            assert_alpha_eq!(run(name, t.clone()), app(var(2), var(1)));
+        }
+    }
+
+    #[test]
+    fn strictness() {
+        // (λx y . y) ((λx . x x) (λx . x x)) is normalizable, but not strictly
+        let t = app(abs(0, abs(1, var(1))), omega());
+        assert_alpha_eq!(run("normal", t.clone()), abs(1, var(1)));
+        assert_alpha_eq!(run("call-by-name", t.clone()), abs(1, var(1)));
+        for name in ["applicative", "weak-call-by-value", "leftmost-innermost"] {
+            let mut t = t.clone();
+            let res = t.normalize(by_name(name).unwrap(), Fuel::steps(100));
+            assert_eq!(res.outcome, Outcome::OutOfFuel, "{name} normalized {t}");
+        }
+    }
+
+    #[test]
+    fn beta_eta_normal_form() {
+        // λx . (λy z . y z) x ~> λx z . x z ~> λx . x
+        let mut t = abs(0, app(abs(1, abs(2, app(var(1), var(2)))), var(0)));
+        let res = t.normalize(&WithEta(Normal), Fuel::UNBOUNDED);
+        assert_eq!(res.outcome, Outcome::Normal);
+        assert_eq!(res.steps, 2);
+        assert_alpha_eq!(t, abs(0, var(0)));
+    }
+
+    #[test]
+    fn steps_contract_the_next_redex() {
+        // (λx . (λy . x y) ((λz . z) x)) (λw . w)
+        let id = |v| abs(v, var(v));
+        let t = app(
+            abs(0, app(abs(1, app(var(0), var(1))), app(id(2), var(0)))),
+            id(3),
+        );
+        // λv . (λw . u w) v
+        let eta = abs(4, app(abs(3, app(var(5), var(3))), var(4)));
+        let mut all: Vec<&dyn ReductionStrategy> = STRATEGIES.to_vec();
+        all.push(&Eta);
+        for s in all {
+            for mut stepped in [t.clone(), eta.clone()] {
+                let mut contracted = stepped.clone();
+                while let Some(p) = s.next_redex(&contracted) {
+                    let by_path = if s.name() == "eta" {
+                        contracted.subterm_mut(&p).unwrap().eta_contract()
+                    } else {
+                        contracted.contract_at(&p)
+                    };
+                    assert!(by_path, "{} found no redex at {p}", s.name());
+                    assert!(!s.step(&mut stepped));
+                    assert_alpha_eq!(stepped, contracted, "{} diverged at {p}", s.name());
+                }
+                assert!(s.step(&mut stepped));
+            }
+        }
+    }
+}
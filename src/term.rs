@@ -1,8 +1,12 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::{self, Write},
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicBool, Ordering},
 };
 
+use crate::{alias::Naming, loops::LoopDetector, strategy::ReductionStrategy, Ref};
+
 /// An lambda body's expression
 /// x is a variable.
 /// M N is an application where M and N are lambda terms.
@@ -12,6 +16,9 @@ pub enum Body {
     Var(usize),
     App(Term, Term),
     Abs(usize, Term),
+    /// A reference to a global definition, unfolded when it's applied, or by
+    /// the strong strategies when its definition isn't normal
+    Ref(Ref),
 }
 
 #[derive(Eq, Clone, PartialEq, Debug, Hash)]
@@ -19,6 +26,135 @@ pub struct Term {
     pub body: Box<Body>,
 }
 
+/// A `Term` compared and hashed modulo the names of its bound variables,
+/// so it can be used as a key of maps where alpha-equivalent terms are the same.
+#[repr(transparent)]
+#[derive(Clone, Debug)]
+pub struct AlphaTerm(pub Term);
+
+impl AlphaTerm {
+    /// Views a `&Term` as an `&AlphaTerm`, useful to query maps without cloning the term
+    pub fn from_ref(t: &Term) -> &Self {
+        // SAFETY: `AlphaTerm` is a `#[repr(transparent)]` wrapper over `Term`,
+        // so both have the same layout.
+        unsafe { &*(t as *const Term as *const Self) }
+    }
+}
+
+impl From<Term> for AlphaTerm {
+    fn from(value: Term) -> Self {
+        Self(value)
+    }
+}
+
+impl PartialEq for AlphaTerm {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.alpha_eq(&other.0)
+    }
+}
+
+impl Eq for AlphaTerm {}
+
+impl Hash for AlphaTerm {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.alpha_hash(state)
+    }
+}
+
+/// Budget of a normalization, it stops when one of the limits is reached.
+/// `usize::MAX` means no limit at all.
+#[derive(Clone, Copy, Debug)]
+pub struct Fuel {
+    /// Max number of β-reductions
+    pub steps: usize,
+    /// Max number of nodes that an intermediate term can have. The evaluators
+    /// that build none, like `Term::normalize_by_need`, bound their normal form
+    /// instead, and leave the term as it was when they stop before reaching it.
+    pub size: usize,
+    /// Stops when the term loops, see `Outcome::Cycle` and `Outcome::Diverges`.
+    /// Just the step by step normalizations can detect it.
+    pub loops: bool,
+    /// Stops with `Outcome::Interrupted` once it's set, like by a signal handler
+    pub interrupt: Option<&'static AtomicBool>,
+}
+
+impl Fuel {
+    pub const UNBOUNDED: Self = Self {
+        steps: usize::MAX,
+        size: usize::MAX,
+        loops: false,
+        interrupt: None,
+    };
+
+    pub fn steps(steps: usize) -> Self {
+        Self {
+            steps,
+            ..Self::UNBOUNDED
+        }
+    }
+
+    pub fn with_size(self, size: usize) -> Self {
+        Self { size, ..self }
+    }
+
+    pub fn detecting_loops(self) -> Self {
+        Self {
+            loops: true,
+            ..self
+        }
+    }
+
+    pub fn interruptible(self, interrupt: &'static AtomicBool) -> Self {
+        Self {
+            interrupt: Some(interrupt),
+            ..self
+        }
+    }
+
+    /// Checks if another step can be taken after `steps` ones
+    pub(crate) fn check(&self, steps: usize) -> Result<(), Outcome> {
+        if steps >= self.steps {
+            Err(Outcome::OutOfFuel)
+        } else if self.interrupt.is_some_and(|i| i.load(Ordering::Relaxed)) {
+            Err(Outcome::Interrupted)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for Fuel {
+    fn default() -> Self {
+        Self::UNBOUNDED
+    }
+}
+
+/// Why a normalization has stopped
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The term is on its normal form
+    Normal,
+    /// The steps budget was exhausted before the normal form was found
+    OutOfFuel,
+    /// An intermediate term got bigger than the size limit
+    SizeExceeded,
+    /// The term after the `start`-th step came back after `period` more steps.
+    /// Just the cycles through the latest terms are found, see `loops::SEEN_NODES`.
+    Cycle { start: usize, period: usize },
+    /// The same redex kept reproducing itself, on the same arguments, while the term grew,
+    /// see `loops::GROWTH_LIMIT`
+    Diverges,
+    /// `Fuel::interrupt` was set
+    Interrupted,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Normalization {
+    pub outcome: Outcome,
+    /// Number of β-reductions applied, and of references unfolded
+    pub steps: usize,
+}
+
 impl Term {
     pub fn coerce(&self, f: impl Fn(&mut Self)) -> Self {
         let mut clone = self.clone();
@@ -26,6 +162,66 @@ impl Term {
         clone
     }
 
+    /// Number of nodes (variables, applications, abstractions and references) of the term
+    pub fn size(&self) -> usize {
+        match self.body.as_ref() {
+            Body::Var(..) | Body::Ref(..) => 1,
+            Body::App(m, n) => 1 + m.size() + n.size(),
+            Body::Abs(_, m) => 1 + m.size(),
+        }
+    }
+
+    /// Number of nodes in the longest branch of the term
+    pub fn depth(&self) -> usize {
+        match self.body.as_ref() {
+            Body::Var(..) | Body::Ref(..) => 1,
+            Body::App(m, n) => 1 + m.depth().max(n.depth()),
+            Body::Abs(_, m) => 1 + m.depth(),
+        }
+    }
+
+    /// Number of subterms, counting the term itself, that satisfy `pred`
+    fn count(&self, pred: &impl Fn(&Self) -> bool) -> usize {
+        let inner = match self.body.as_ref() {
+            Body::Var(..) | Body::Ref(..) => 0,
+            Body::App(m, n) => m.count(pred) + n.count(pred),
+            Body::Abs(_, m) => m.count(pred),
+        };
+        inner + pred(self) as usize
+    }
+
+    /// Number of occurrences of variables
+    pub fn var_count(&self) -> usize {
+        self.count(&|t| matches!(t.body.as_ref(), Body::Var(..)))
+    }
+
+    pub fn app_count(&self) -> usize {
+        self.count(&|t| matches!(t.body.as_ref(), Body::App(..)))
+    }
+
+    pub fn abs_count(&self) -> usize {
+        self.count(&|t| matches!(t.body.as_ref(), Body::Abs(..)))
+    }
+
+    /// Number of β-redexes, same as `Term::redexes().len()`
+    pub fn redex_count(&self) -> usize {
+        self.count(&Self::is_redex)
+    }
+
+    /// Most abstractions that enclose a subterm
+    pub fn binder_nesting(&self) -> usize {
+        match self.body.as_ref() {
+            Body::Var(..) | Body::Ref(..) => 0,
+            Body::App(m, n) => m.binder_nesting().max(n.binder_nesting()),
+            Body::Abs(_, m) => 1 + m.binder_nesting(),
+        }
+    }
+
+    /// Checks if the term doesn't have free variables
+    pub fn is_closed(&self) -> bool {
+        self.free_vars().is_empty()
+    }
+
     pub fn bounded_vars(&self) -> HashSet<usize> {
         let mut bounds = HashSet::new();
         self.bounded_vars_from(&mut bounds);
@@ -34,7 +230,7 @@ impl Term {
 
     fn bounded_vars_from(&self, set: &mut HashSet<usize>) {
         match self.body.as_ref() {
-            Body::Var(..) => (),
+            Body::Var(..) | Body::Ref(..) => (),
             Body::App(m, n) => {
                 m.bounded_vars_from(set);
                 n.bounded_vars_from(set);
@@ -59,6 +255,7 @@ impl Term {
                     frees.insert(*v);
                 }
             }
+            Body::Ref(..) => (),
             Body::App(m, n) => {
                 m.free_vars_from(closeds, frees);
                 n.free_vars_from(closeds, frees);
@@ -73,6 +270,69 @@ impl Term {
         }
     }
 
+    /// Checks if both terms are equal modulo the names of their bound variables
+    pub fn alpha_eq(&self, other: &Self) -> bool {
+        self.alpha_eq_with(other, &mut Vec::new(), &mut Vec::new())
+    }
+
+    fn alpha_eq_with(
+        &self,
+        other: &Self,
+        lbinds: &mut Vec<usize>,
+        rbinds: &mut Vec<usize>,
+    ) -> bool {
+        match (self.body.as_ref(), other.body.as_ref()) {
+            (Body::Var(l), Body::Var(r)) => {
+                let lpos = lbinds.iter().rev().position(|b| b == l);
+                let rpos = rbinds.iter().rev().position(|b| b == r);
+                match (lpos, rpos) {
+                    (None, None) => l == r,
+                    (lpos, rpos) => lpos == rpos,
+                }
+            }
+            (Body::App(lm, ln), Body::App(rm, rn)) => {
+                lm.alpha_eq_with(rm, lbinds, rbinds) && ln.alpha_eq_with(rn, lbinds, rbinds)
+            }
+            (Body::Ref(l), Body::Ref(r)) => l == r,
+            (Body::Abs(lv, lm), Body::Abs(rv, rm)) => {
+                lbinds.push(*lv);
+                rbinds.push(*rv);
+                let eq = lm.alpha_eq_with(rm, lbinds, rbinds);
+                lbinds.pop();
+                rbinds.pop();
+                eq
+            }
+            _ => false,
+        }
+    }
+
+    /// Hashes the term consistently with `Term::alpha_eq`,
+    /// bound variables are hashed by the distance to their binders
+    pub fn alpha_hash<H: Hasher>(&self, state: &mut H) {
+        self.alpha_hash_with(&mut Vec::new(), state)
+    }
+
+    fn alpha_hash_with<H: Hasher>(&self, binds: &mut Vec<usize>, state: &mut H) {
+        match self.body.as_ref() {
+            Body::Var(v) => match binds.iter().rev().position(|b| b == v) {
+                Some(idx) => (0u8, idx).hash(state),
+                None => (1u8, v).hash(state),
+            },
+            Body::App(m, n) => {
+                2u8.hash(state);
+                m.alpha_hash_with(binds, state);
+                n.alpha_hash_with(binds, state);
+            }
+            Body::Abs(v, m) => {
+                3u8.hash(state);
+                binds.push(*v);
+                m.alpha_hash_with(binds, state);
+                binds.pop();
+            }
+            Body::Ref(r) => (4u8, r).hash(state),
+        }
+    }
+
     pub fn unique_alpha_redex(&mut self) {
         let frees = self.free_vars();
         self.unique_alpha_replace(&mut 0, &mut HashMap::new(), &frees);
@@ -92,6 +352,7 @@ impl Term {
                     debug_assert!(frees.contains(v));
                 }
             }
+            Body::Ref(..) => (),
             Body::App(m, n) => {
                 m.unique_alpha_replace(next, replaces, frees);
                 n.unique_alpha_replace(next, replaces, frees);
@@ -101,12 +362,84 @@ impl Term {
                 // so it's impossible to have a already used replacement index while reducing,
                 // just the `frees` can appear.
                 let nv = (*next..).find(|n| !frees.contains(n)).unwrap();
-                replaces.insert(*v, nv);
-                *v = nv;
+                let shadowed = replaces.insert(*v, nv);
+                let old = std::mem::replace(v, nv);
                 *next = nv + 1;
                 m.unique_alpha_replace(next, replaces, frees);
+                match shadowed {
+                    Some(sv) => replaces.insert(old, sv),
+                    None => replaces.remove(&old),
+                };
+            }
+        }
+    }
+
+    /// Contracts the term if it's a β-redex, i.e `(λx.M) N` becomes `M[x := N]`,
+    /// or unfolds the applied reference of `R N` to its definition, as well as
+    /// a reference whose definition isn't normal,
+    /// returns a `bool` indicating if it was a redex.
+    pub fn contract(&mut self) -> bool {
+        if let Body::Ref(r) = self.body.as_ref() {
+            if r.is_normal() {
+                return false;
+            }
+            *self = r.term().clone();
+            return true;
+        }
+        if let Body::App(m, n) = self.body.as_mut() {
+            match m.body.as_mut() {
+                Body::Abs(v, b) => {
+                    b.apply(*v, n);
+                    let b = std::mem::replace(b, Body::Var(*v).into());
+                    *self = b;
+                    return true;
+                }
+                Body::Ref(r) => {
+                    *m = r.term().clone();
+                    return true;
+                }
+                _ => (),
+            }
+        }
+        false
+    }
+
+    /// Contracts the term if it's an η-redex, i.e `λx.M x` becomes `M` when `x ∉ FREE(M)`,
+    /// returns a `bool` indicating if it was a redex.
+    pub fn eta_contract(&mut self) -> bool {
+        if !self.is_eta_redex() {
+            return false;
+        }
+        if let Body::Abs(v, b) = self.body.as_mut() {
+            if let Body::App(m, _) = b.body.as_mut() {
+                let m = std::mem::replace(m, Body::Var(*v).into());
+                *self = m;
             }
         }
+        true
+    }
+
+    /// Applicates an η-reduction on the leftmost outmost η-redex,
+    /// returns a `bool` indicating if it's on its η-normal form.
+    pub fn eta_redex_step(&mut self) -> bool {
+        if self.eta_contract() {
+            return false;
+        }
+        match self.body.as_mut() {
+            Body::App(m, n) => m.eta_redex_step() && n.eta_redex_step(),
+            Body::Abs(_, m) => m.eta_redex_step(),
+            Body::Var(..) | Body::Ref(..) => true,
+        }
+    }
+
+    /// Expands the term to `λx.M x`, being `x` a variable that's not used by `M`
+    pub fn eta_expand(&mut self) {
+        let (frees, bounds) = (self.free_vars(), self.bounded_vars());
+        let v = (0..)
+            .find(|v| !frees.contains(v) && !bounds.contains(v))
+            .unwrap();
+        let m = std::mem::replace(self, Body::Var(v).into());
+        *self = Body::Abs(v, Body::App(m, Body::Var(v).into()).into()).into();
     }
 
     /// Applicates the normal β-reduction on the term,
@@ -114,49 +447,74 @@ impl Term {
     /// returns a `bool` indicating if it's on its normal form (a.f.k irreducible)
     /// I.e, it computes the function before each argument.
     pub fn normal_beta_redex_step(&mut self) -> bool {
+        if self.contract() {
+            return false;
+        }
         match self.body.as_mut() {
-            Body::App(m, n) => {
-                if m.normal_beta_redex_step() {
-                    if let Body::Abs(v, b) = m.body.as_mut() {
-                        b.apply(*v, n);
-                        *self = b.clone(); // FIXME: Shouldn't clone
-                        false
-                    } else {
-                        true
-                    }
-                } else {
-                    false
-                }
-            }
+            Body::App(m, n) => m.normal_beta_redex_step() && n.normal_beta_redex_step(),
             Body::Abs(_, m) => m.normal_beta_redex_step(),
-            Body::Var(..) => true,
+            Body::Var(..) | Body::Ref(..) => true,
         }
     }
 
-    /// Applicates the call-by-value β-reduction on the term,
+    /// Applicates the applicative order (a strict call-by-value) β-reduction on the term,
     /// where the innermost right term is evaluated first,
     /// returns a `bool` indicating if it's on its normal form (a.f.k irreducible)
     /// I.e, it computes the arguments before function.
     pub fn cbv_beta_redex_step(&mut self) -> bool {
         match self.body.as_mut() {
             Body::App(m, n) => {
-                if n.cbv_beta_redex_step() {
-                    if let Body::Abs(v, b) = m.body.as_mut() {
-                        b.apply(*v, n);
-                        *self = b.clone(); // FIXME: Shouldn't clone
-                        false
-                    } else {
-                        true
-                    }
-                } else {
-                    false
-                }
+                n.cbv_beta_redex_step() && m.cbv_beta_redex_step() && !self.contract()
             }
             Body::Abs(_, m) => m.cbv_beta_redex_step(),
             Body::Var(..) => true,
+            Body::Ref(..) => !self.contract(),
         }
     }
 
+    /// Reduces the term until it's irreducible by `strategy` or exhausts the `fuel`
+    pub fn normalize<S>(&mut self, strategy: &S, fuel: Fuel) -> Normalization
+    where
+        S: ReductionStrategy + ?Sized,
+    {
+        self.normalize_with(strategy, fuel, |_| ())
+    }
+
+    /// Same as `Term::normalize`, but calls `inspect` with each intermediate term.
+    pub fn normalize_with<S>(
+        &mut self,
+        strategy: &S,
+        fuel: Fuel,
+        mut inspect: impl FnMut(&Self),
+    ) -> Normalization
+    where
+        S: ReductionStrategy + ?Sized,
+    {
+        let mut steps = 0;
+        let mut detector = fuel.loops.then(|| LoopDetector::new(self));
+        let outcome = loop {
+            if let Err(outcome) = fuel.check(steps) {
+                break outcome;
+            }
+            let redex = detector
+                .as_ref()
+                .and_then(|_| strategy.next_redex(self))
+                .and_then(|p| Some((self.subterm(&p)?.clone(), p)));
+            if strategy.step(self) {
+                break Outcome::Normal;
+            }
+            steps += 1;
+            inspect(self);
+            if fuel.size != usize::MAX && self.size() > fuel.size {
+                break Outcome::SizeExceeded;
+            }
+            if let Some(outcome) = detector.as_mut().and_then(|d| d.watch(self, redex, steps)) {
+                break outcome;
+            }
+        };
+        Normalization { outcome, steps }
+    }
+
     /// checks if VARS(`self`) ⊂ FREE(`val`)
     /// if then, so it alpha-redex `self` to don't match with the already used variables
     pub fn safe_context_check(&mut self, val: &Self) {
@@ -181,6 +539,7 @@ impl Term {
                     *self = val.clone();
                 }
             }
+            Body::Ref(..) => (),
             Body::App(m, n) => {
                 m.apply(var, val);
                 n.apply(var, val);
@@ -202,38 +561,74 @@ impl From<Body> for Term {
     }
 }
 
+/// Writes the name of a variable, as chosen by `Naming::DISPLAY`
 pub fn write_alias(idx: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    fmt::Display::fmt(&idx, f)
+    Naming::DISPLAY.write(idx, f)
 }
 
-impl fmt::Display for Term {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Term {
+    fn fmt_with(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        paren_app: bool,
+        paren_abs: bool,
+    ) -> fmt::Result {
         match self.body.as_ref() {
             Body::Var(v) => write_alias(*v, f),
-            Body::App(m, n) => write!(
-                f,
-                "{} {n}",
-                if !matches!(m.body.as_ref(), Body::Var(..)) {
-                    format!("({m})")
-                } else {
-                    m.to_string()
+            Body::Ref(..) => unreachable!("references are inlined before printing"),
+            Body::App(m, n) => {
+                if paren_app {
+                    f.write_char('(')?;
                 }
-            ),
+                m.fmt_with(f, false, true)?;
+                f.write_char(' ')?;
+                n.fmt_with(f, true, paren_abs && !paren_app)?;
+                if paren_app {
+                    f.write_char(')')?;
+                }
+                Ok(())
+            }
             Body::Abs(v, m) => {
+                if paren_abs {
+                    f.write_char('(')?;
+                }
                 f.write_str("λ")?;
                 write_alias(*v, f)?;
                 f.write_char('.')?;
-                write!(f, "{m}")
+                m.fmt_with(f, false, false)?;
+                if paren_abs {
+                    f.write_char(')')?;
+                }
+                Ok(())
             }
         }
     }
 }
 
+/// Writes just the needed parentheses, so it's read back by `Term::from_str`:
+/// applications are left associative and abstractions extend to the right,
+/// like `(λ0.0 0) (1 2) λ3.3`. References are inlined, as the parser doesn't
+/// know their names.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.has_refs() {
+            self.inline_refs().fmt_with(f, false, false)
+        } else {
+            self.fmt_with(f, false, false)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::assert_alpha_eq;
+    use crate::strategy::{Applicative, Normal};
+
+    use std::collections::HashSet;
+
+    use std::sync::atomic::AtomicBool;
 
-    use super::{Body, Term};
+    use super::{AlphaTerm, Body, Fuel, Outcome, Term};
 
     #[test]
     fn capture_free_subst() {
@@ -270,7 +665,147 @@ mod tests {
             .into(),
         )
         .into();
-        while !redex.normal_beta_redex_step() {}
+        let res = redex.normalize(&Normal, Fuel::UNBOUNDED);
+        assert_eq!(res.outcome, Outcome::Normal);
         assert_alpha_eq!(redex, expected);
     }
+
+    // these terms have no references, the only part of a term that clippy sees as mutable
+    #[allow(clippy::mutable_key_type)]
+    #[test]
+    fn alpha_equivalence() {
+        // λx . x y
+        let lhs: Term = Body::Abs(
+            0,
+            Body::App(Body::Var(0).into(), Body::Var(1).into()).into(),
+        )
+        .into();
+        // λz . z y
+        let rhs: Term = Body::Abs(
+            2,
+            Body::App(Body::Var(2).into(), Body::Var(1).into()).into(),
+        )
+        .into();
+        // λy . y y
+        let captured: Term = Body::Abs(
+            1,
+            Body::App(Body::Var(1).into(), Body::Var(1).into()).into(),
+        )
+        .into();
+
+        assert!(lhs.alpha_eq(&rhs));
+        assert!(!lhs.alpha_eq(&captured));
+        assert_ne!(lhs, rhs);
+
+        let set: HashSet<_> = [lhs.clone(), rhs, captured.clone()]
+            .into_iter()
+            .map(AlphaTerm)
+            .collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(AlphaTerm::from_ref(&lhs)));
+        assert!(set.contains(AlphaTerm::from_ref(&captured)));
+
+        // λx . (λx . x) x keeps referring to the outer `x` after renaming
+        let shadowing: Term = Body::Abs(
+            0,
+            Body::App(
+                Body::Abs(0, Body::Var(0).into()).into(),
+                Body::Var(0).into(),
+            )
+            .into(),
+        )
+        .into();
+        assert!(shadowing
+            .coerce(Term::unique_alpha_redex)
+            .alpha_eq(&shadowing));
+    }
+
+    #[test]
+    fn eta_conversion() {
+        // λx . y x
+        let mut t: Term = Body::Abs(
+            0,
+            Body::App(Body::Var(1).into(), Body::Var(0).into()).into(),
+        )
+        .into();
+        assert!(t.eta_contract());
+        assert_eq!(t, Body::Var(1).into());
+        t.eta_expand();
+        assert_eq!(
+            t,
+            Body::Abs(
+                0,
+                Body::App(Body::Var(1).into(), Body::Var(0).into()).into()
+            )
+            .into()
+        );
+
+        // λx . x x
+        let mut t: Term = Body::Abs(
+            0,
+            Body::App(Body::Var(0).into(), Body::Var(0).into()).into(),
+        )
+        .into();
+        assert!(!t.eta_contract());
+        assert!(t.eta_redex_step());
+    }
+
+    #[test]
+    fn omega_runs_out_of_fuel() {
+        // (λx . x x) (λx . x x)
+        let delta: Term = Body::Abs(
+            0,
+            Body::App(Body::Var(0).into(), Body::Var(0).into()).into(),
+        )
+        .into();
+        let mut omega: Term = Body::App(delta.clone(), delta).into();
+
+        let res = omega.normalize(&Normal, Fuel::steps(100));
+        assert_eq!(res.outcome, Outcome::OutOfFuel);
+        assert_eq!(res.steps, 100);
+
+        let res = omega.normalize(&Applicative, Fuel::steps(100).with_size(4));
+        assert_eq!(res.outcome, Outcome::SizeExceeded);
+        assert_eq!(res.steps, 1);
+
+        static INTERRUPT: AtomicBool = AtomicBool::new(true);
+        for res in [
+            omega.normalize(&Normal, Fuel::UNBOUNDED.interruptible(&INTERRUPT)),
+            omega.normalize_krivine(Fuel::UNBOUNDED.interruptible(&INTERRUPT)),
+        ] {
+            assert_eq!(res.outcome, Outcome::Interrupted);
+            assert_eq!(res.steps, 0);
+        }
+    }
+
+    #[test]
+    fn metrics() {
+        // (λx . x x) (λy . λz . y w)
+        let t: Term = Body::App(
+            Body::Abs(
+                0,
+                Body::App(Body::Var(0).into(), Body::Var(0).into()).into(),
+            )
+            .into(),
+            Body::Abs(
+                1,
+                Body::Abs(
+                    2,
+                    Body::App(Body::Var(1).into(), Body::Var(3).into()).into(),
+                )
+                .into(),
+            )
+            .into(),
+        )
+        .into();
+        assert_eq!(t.size(), 10);
+        assert_eq!(t.depth(), 5);
+        assert_eq!((t.var_count(), t.app_count(), t.abs_count()), (4, 3, 3));
+        assert_eq!(t.var_count() + t.app_count() + t.abs_count(), t.size());
+        assert_eq!(t.redex_count(), 1);
+        assert_eq!(t.binder_nesting(), 2);
+        assert!(!t.is_closed());
+        assert!(Term::church_nat(3).is_closed());
+        assert_eq!(Term::church_nat(3).redex_count(), 0);
+    }
 }
@@ -0,0 +1,229 @@
+use std::{cell::RefCell, fmt::Write};
+
+use crate::{Fuel, Normalization, Path, ReductionStrategy, Term};
+
+/// A contraction of a reduction
+#[derive(Clone, Debug)]
+pub struct TraceStep {
+    /// Name of the strategy that chose the redex
+    pub strategy: &'static str,
+    pub position: Path,
+    /// The redex before being contracted
+    pub redex: Term,
+    /// What the redex was contracted to
+    pub contractum: Term,
+    pub size_before: usize,
+    pub size_after: usize,
+}
+
+impl TraceStep {
+    /// Contraction of `redex` to `contractum`, in a term of `size_before` nodes
+    pub fn new(
+        strategy: &'static str,
+        position: Path,
+        redex: Term,
+        contractum: Term,
+        size_before: usize,
+    ) -> Self {
+        Self {
+            strategy,
+            position,
+            size_after: size_before - redex.size() + contractum.size(),
+            redex,
+            contractum,
+            size_before,
+        }
+    }
+}
+
+/// The steps of a reduction, from its initial term
+#[derive(Clone, Debug)]
+pub struct Trace {
+    pub initial: Term,
+    pub steps: Vec<TraceStep>,
+}
+
+impl Trace {
+    pub fn new(initial: Term) -> Self {
+        Self {
+            initial,
+            steps: Vec::new(),
+        }
+    }
+
+    /// The term after each step, rebuilt by replaying the steps on the initial term
+    pub fn terms(&self) -> impl Iterator<Item = Term> + '_ {
+        self.steps.iter().scan(self.initial.clone(), |t, step| {
+            *t.subterm_mut(&step.position)
+                .expect("the redex isn't part of the term") = step.contractum.clone();
+            Some(t.clone())
+        })
+    }
+
+    /// The term reached by the last step
+    pub fn last(&self) -> Term {
+        self.terms().last().unwrap_or_else(|| self.initial.clone())
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        json.push_str("{\n");
+        writeln!(json, "  \"initial\": {},", json_str(&self.initial)).unwrap();
+        json.push_str("  \"steps\": [");
+        for (i, (step, term)) in self.steps.iter().zip(self.terms()).enumerate() {
+            json.push_str(if i == 0 { "\n" } else { ",\n" });
+            json.push_str("    {");
+            write!(
+                json,
+                "\"strategy\": {}, \"position\": {}, \"redex\": {}, \
+                 \"size_before\": {}, \"size_after\": {}, \"term\": {}",
+                json_str(step.strategy),
+                json_str(&step.position),
+                json_str(&step.redex),
+                step.size_before,
+                step.size_after,
+                json_str(&term)
+            )
+            .unwrap();
+            json.push('}');
+        }
+        if !self.steps.is_empty() {
+            json.push_str("\n  ");
+        }
+        json.push_str("]\n}\n");
+        json
+    }
+}
+
+fn json_str(s: impl ToString) -> String {
+    let mut json = String::from('"');
+    for c in s.to_string().chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Records each step of the inner strategy
+struct Recorder<'a, S: ?Sized> {
+    strategy: &'a S,
+    steps: RefCell<Vec<TraceStep>>,
+}
+
+impl<S: ReductionStrategy + ?Sized> ReductionStrategy for Recorder<'_, S> {
+    fn name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    fn next_redex(&self, t: &Term) -> Option<Path> {
+        self.strategy.next_redex(t)
+    }
+
+    fn step(&self, t: &mut Term) -> bool {
+        let Some(position) = self.strategy.next_redex(t) else {
+            return self.strategy.step(t);
+        };
+        let size_before = t.size();
+        let redex = t
+            .subterm(&position)
+            .expect("the redex isn't part of the term");
+        let redex = redex.clone();
+        let irreducible = self.strategy.step(t);
+        debug_assert!(!irreducible, "{} didn't contract {position}", self.name());
+        let contractum = t.subterm(&position).unwrap().clone();
+        let step = TraceStep::new(self.name(), position, redex, contractum, size_before);
+        self.steps.borrow_mut().push(step);
+        irreducible
+    }
+}
+
+impl Term {
+    /// Same as `Term::normalize_with`, but also records the `Trace` of the reduction
+    pub fn normalize_traced<S>(
+        &mut self,
+        strategy: &S,
+        fuel: Fuel,
+        inspect: impl FnMut(&Self),
+    ) -> (Normalization, Trace)
+    where
+        S: ReductionStrategy + ?Sized,
+    {
+        let mut trace = Trace::new(self.clone());
+        let recorder = Recorder {
+            strategy,
+            steps: RefCell::default(),
+        };
+        let res = self.normalize_with(&recorder, fuel, inspect);
+        trace.steps = recorder.steps.into_inner();
+        (res, trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fixtures::{abs, app, var},
+        strategy::{Applicative, Normal},
+        Fuel, Outcome,
+    };
+
+    #[test]
+    fn records_steps() {
+        // (λx . x) ((λy . y) z)
+        let mut t = app(abs(0, var(0)), app(abs(1, var(1)), var(2)));
+        let (res, trace) = t.normalize_traced(&Normal, Fuel::UNBOUNDED, |_| ());
+        assert_eq!(res.outcome, Outcome::Normal);
+        assert_eq!(trace.steps.len(), res.steps);
+        assert_eq!(trace.last(), var(2));
+
+        let sizes: Vec<_> = trace
+            .steps
+            .iter()
+            .map(|s| (s.position.to_string(), s.size_before, s.size_after))
+            .collect();
+        assert_eq!(sizes, [("ε".into(), 7, 4), ("ε".into(), 4, 1)]);
+        assert_eq!(trace.steps[0].redex, trace.initial);
+    }
+
+    #[test]
+    fn replays_the_steps() {
+        // λw . (λx . x x) ((λy . y) w)
+        let mut t = abs(
+            3,
+            app(abs(0, app(var(0), var(0))), app(abs(1, var(1)), var(3))),
+        );
+        let mut seen = Vec::new();
+        let (_, trace) =
+            t.normalize_traced(&Applicative, Fuel::UNBOUNDED, |t| seen.push(t.clone()));
+        let positions: Vec<_> = trace.steps.iter().map(|s| s.position.to_string()).collect();
+        assert_eq!(positions, ["ba", "b"]);
+        assert_eq!(trace.terms().collect::<Vec<_>>(), seen);
+        assert_eq!(trace.last(), t);
+    }
+
+    #[test]
+    fn json_export() {
+        // (λx . x) y
+        let mut t = app(abs(0, var(0)), var(1));
+        let (_, trace) = t.normalize_traced(&Normal, Fuel::UNBOUNDED, |_| ());
+        let (initial, redex, term) = (
+            trace.initial.to_string(),
+            trace.steps[0].redex.to_string(),
+            trace.last().to_string(),
+        );
+        assert_eq!(
+            trace.to_json(),
+            format!(
+                "{{\n  \"initial\": \"{initial}\",\n  \"steps\": [\n    \
+                 {{\"strategy\": \"normal\", \"position\": \"ε\", \"redex\": \"{redex}\", \
+                 \"size_before\": 4, \"size_after\": 1, \"term\": \"{term}\"}}\n  ]\n}}\n"
+            )
+        );
+    }
+}